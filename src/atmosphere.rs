@@ -0,0 +1,179 @@
+use bevy::color::Mix;
+use bevy::color::palettes::tailwind::*;
+use bevy::prelude::*;
+
+use crate::level::StartLevel;
+use crate::weather::{Sun, Weather};
+
+pub fn atmosphere_plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (
+            advance_time_of_day,
+            update_lpf_atmosphere,
+            update_sight_range,
+        )
+            .chain(),
+    );
+
+    app.add_systems(Update, (update_sky_color, update_sun, update_camera_fog));
+
+    app.add_observer(reset_atmosphere);
+
+    app.insert_resource(TimeOfDay {
+        hours: START_HOUR,
+        hours_per_second: 0.02,
+    });
+    app.insert_resource(Atmosphere::for_weather(Weather::Clear));
+    app.insert_resource(SightRange(
+        Atmosphere::for_weather(Weather::Clear).sight_range,
+    ));
+}
+
+/// Time of day when a level starts.
+const START_HOUR: f32 = 10.0;
+
+/// Clock time in hours, [0, 24).
+#[derive(Resource, Debug)]
+pub struct TimeOfDay {
+    pub hours: f32,
+    pub hours_per_second: f32,
+}
+
+impl TimeOfDay {
+    /// Angle of the sun above the horizon; negative at night.
+    pub fn sun_elevation(&self) -> f32 {
+        (self.hours - 6.0) / 12.0 * std::f32::consts::PI
+    }
+
+    /// 1.0 during the day, fading to a moonlit minimum around dusk and dawn.
+    pub fn daylight(&self) -> f32 {
+        (self.sun_elevation().sin() * 3.0).clamp(0.05, 1.0)
+    }
+}
+
+/// The look of the sky for the current weather. Eases towards the values
+/// from [`Atmosphere::for_weather`] whenever the weather changes.
+#[derive(Resource, Debug, Clone)]
+pub struct Atmosphere {
+    pub sky: Srgba,
+    pub fog: Srgba,
+    /// Distance in meters at which the fog hides things completely.
+    pub fog_visibility: f32,
+    pub sun_illuminance: f32,
+    pub sun_color: Srgba,
+    /// How far a duck can make out another duck in broad daylight.
+    pub sight_range: f32,
+}
+
+const NIGHT_SKY: Srgba = SLATE_900;
+
+impl Atmosphere {
+    pub fn for_weather(weather: Weather) -> Self {
+        match weather {
+            Weather::Clear => Self {
+                sky: BLUE_300,
+                fog: SKY_200,
+                fog_visibility: 3000.0,
+                sun_illuminance: light_consts::lux::OVERCAST_DAY,
+                sun_color: Srgba::WHITE,
+                sight_range: 1000.0,
+            },
+            Weather::Fog => Self {
+                sky: GRAY_400,
+                fog: GRAY_300,
+                fog_visibility: 60.0,
+                sun_illuminance: light_consts::lux::OVERCAST_DAY / 4.0,
+                sun_color: Srgba::WHITE,
+                sight_range: 25.0,
+            },
+            Weather::Thunderstorm => Self {
+                sky: GRAY_700,
+                fog: GRAY_600,
+                fog_visibility: 400.0,
+                sun_illuminance: light_consts::lux::FULL_MOON_NIGHT,
+                sun_color: Srgba::BLACK,
+                sight_range: 60.0,
+            },
+        }
+    }
+
+    fn lerp(&mut self, target: &Self, t: f32) {
+        self.sky = self.sky.mix(&target.sky, t);
+        self.fog = self.fog.mix(&target.fog, t);
+        self.fog_visibility = self.fog_visibility.lerp(target.fog_visibility, t);
+        self.sun_illuminance = self.sun_illuminance.lerp(target.sun_illuminance, t);
+        self.sun_color = self.sun_color.mix(&target.sun_color, t);
+        self.sight_range = self.sight_range.lerp(target.sight_range, t);
+    }
+}
+
+/// How far ducks can currently see, after weather and darkness.
+#[derive(Resource, Debug, Deref)]
+pub struct SightRange(pub f32);
+
+fn advance_time_of_day(mut tod: ResMut<TimeOfDay>, time: Res<Time<Fixed>>) {
+    tod.hours = (tod.hours + tod.hours_per_second * time.delta_secs()).rem_euclid(24.0);
+}
+
+fn update_lpf_atmosphere(mut atmosphere: ResMut<Atmosphere>, weather: Res<State<Weather>>) {
+    let target = Atmosphere::for_weather(**weather);
+    atmosphere.lerp(&target, 0.02);
+}
+
+/// Sight range feeds duck AI, so a restarted level needs the same sky.
+fn reset_atmosphere(
+    _event: On<StartLevel>,
+    mut tod: ResMut<TimeOfDay>,
+    mut atmosphere: ResMut<Atmosphere>,
+) {
+    tod.hours = START_HOUR;
+    *atmosphere = Atmosphere::for_weather(Weather::Clear);
+}
+
+fn update_sight_range(
+    atmosphere: Res<Atmosphere>,
+    tod: Res<TimeOfDay>,
+    mut sight: ResMut<SightRange>,
+) {
+    let night_penalty = 0.5.lerp(1.0, tod.daylight());
+    sight.0 = atmosphere.sight_range.min(atmosphere.fog_visibility) * night_penalty;
+}
+
+fn update_sky_color(
+    atmosphere: Res<Atmosphere>,
+    tod: Res<TimeOfDay>,
+    mut color: ResMut<ClearColor>,
+) {
+    let sky = NIGHT_SKY.mix(&atmosphere.sky, tod.daylight());
+    color.0 = sky.into();
+}
+
+fn update_sun(
+    atmosphere: Res<Atmosphere>,
+    tod: Res<TimeOfDay>,
+    sun: Single<(&mut DirectionalLight, &mut Transform), With<Sun>>,
+) {
+    let (mut light, mut tf) = sun.into_inner();
+
+    let e = tod.sun_elevation();
+    let to_sun = Vec3::new(e.cos(), e.sin(), 0.4).normalize();
+    *tf = Transform::from_translation(to_sun * 50.0).looking_at(Vec3::ZERO, Vec3::Y);
+
+    light.illuminance = atmosphere.sun_illuminance * tod.daylight();
+    light.color = atmosphere.sun_color.into();
+}
+
+fn update_camera_fog(
+    atmosphere: Res<Atmosphere>,
+    tod: Res<TimeOfDay>,
+    fog: Query<&mut DistanceFog>,
+) {
+    let color = NIGHT_SKY.mix(&atmosphere.fog, tod.daylight());
+    let visibility = atmosphere.fog_visibility * 0.5.lerp(1.0, tod.daylight());
+
+    for mut fog in fog {
+        fog.color = color.into();
+        fog.falloff = FogFalloff::from_visibility(visibility);
+    }
+}
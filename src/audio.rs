@@ -0,0 +1,184 @@
+use bevy::audio::{AudioSinkPlayback, Volume};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::persist::DataDir;
+
+pub fn audio_plugin(app: &mut App) {
+    app.add_systems(Startup, load_audio_settings);
+
+    app.add_systems(
+        Update,
+        (
+            save_audio_settings_on_change,
+            update_ambience_ducking,
+            apply_bus_volumes,
+        )
+            .chain(),
+    );
+
+    app.insert_resource(AudioSettings::default());
+    app.insert_resource(AmbienceDucking(1.0));
+    app.init_resource::<DataDir>();
+}
+
+const AUDIO_SETTINGS_FILE: &str = "audio.ron";
+
+/// How far ambience drops while something loud, like thunder, is playing.
+const AMBIENCE_DUCKED_GAIN: f32 = 0.35;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bus {
+    #[allow(unused)] // nothing plays music yet
+    Music,
+    Ambience,
+    Sfx,
+    Voice,
+}
+
+/// Per-bus volumes, all linear in [0, 1]. Saved whenever they change.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub music: f32,
+    pub ambience: f32,
+    pub sfx: f32,
+    pub voice: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            music: 0.8,
+            ambience: 1.0,
+            sfx: 1.0,
+            voice: 1.0,
+        }
+    }
+}
+
+impl AudioSettings {
+    pub const TUNABLES: [&'static str; 5] = ["master", "music", "ambience", "sfx", "voice"];
+
+    pub fn bus_gain(&self, bus: Bus) -> f32 {
+        let gain = match bus {
+            Bus::Music => self.music,
+            Bus::Ambience => self.ambience,
+            Bus::Sfx => self.sfx,
+            Bus::Voice => self.voice,
+        };
+        gain * self.master
+    }
+
+    /// Starting volume for a new sound on the given bus.
+    pub fn volume(&self, bus: Bus, gain: f32) -> Volume {
+        Volume::Linear(self.bus_gain(bus) * gain)
+    }
+
+    fn tunable_mut(&mut self, index: usize) -> Option<&mut f32> {
+        match index {
+            0 => Some(&mut self.master),
+            1 => Some(&mut self.music),
+            2 => Some(&mut self.ambience),
+            3 => Some(&mut self.sfx),
+            4 => Some(&mut self.voice),
+            _ => None,
+        }
+    }
+
+    pub fn tunable(&self, index: usize) -> f32 {
+        match index {
+            0 => self.master,
+            1 => self.music,
+            2 => self.ambience,
+            3 => self.sfx,
+            4 => self.voice,
+            _ => 0.0,
+        }
+    }
+
+    pub fn nudge_tunable(&mut self, index: usize, steps: i32) {
+        if let Some(v) = self.tunable_mut(index) {
+            *v = (*v + steps as f32 * 0.1).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// Routes a sound through a mixer bus. `gain` is the sound's own volume
+/// before the bus and master volumes are applied.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct BusSound {
+    pub bus: Bus,
+    pub gain: f32,
+}
+
+impl BusSound {
+    pub fn new(bus: Bus, gain: f32) -> Self {
+        Self { bus, gain }
+    }
+}
+
+/// Marks a sound that should push the ambience bus down while it plays.
+#[derive(Component, Debug)]
+pub struct DucksAmbience;
+
+#[derive(Resource, Debug, Deref)]
+struct AmbienceDucking(f32);
+
+fn load_audio_settings(mut settings: ResMut<AudioSettings>, data: Res<DataDir>) {
+    match data.load_ron::<AudioSettings>(AUDIO_SETTINGS_FILE) {
+        Ok(loaded) => *settings.bypass_change_detection() = loaded,
+        Err(e) => info!("Using default audio settings: {}", e),
+    }
+}
+
+fn save_audio_settings_on_change(settings: Res<AudioSettings>, data: Res<DataDir>) {
+    if settings.is_changed()
+        && !settings.is_added()
+        && let Err(e) = data.save_ron(AUDIO_SETTINGS_FILE, &*settings)
+    {
+        error!("Failed to save audio settings: {}", e);
+    }
+}
+
+fn update_ambience_ducking(
+    mut ducking: ResMut<AmbienceDucking>,
+    duckers: Query<(), With<DucksAmbience>>,
+    time: Res<Time>,
+) {
+    let target = if duckers.is_empty() {
+        1.0
+    } else {
+        AMBIENCE_DUCKED_GAIN
+    };
+
+    // duck quickly, recover slowly
+    let rate = if target < ducking.0 { 8.0 } else { 1.0 };
+    let t = (rate * time.delta_secs()).min(1.0);
+    ducking.0 += (target - ducking.0) * t;
+}
+
+fn apply_bus_volumes(
+    settings: Res<AudioSettings>,
+    ducking: Res<AmbienceDucking>,
+    sinks: Query<(&BusSound, &mut AudioSink)>,
+    spatial_sinks: Query<(&BusSound, &mut SpatialAudioSink)>,
+) {
+    let volume = |sound: &BusSound| {
+        let duck = if sound.bus == Bus::Ambience {
+            **ducking
+        } else {
+            1.0
+        };
+        settings.volume(sound.bus, sound.gain * duck)
+    };
+
+    for (sound, mut sink) in sinks {
+        sink.set_volume(volume(sound));
+    }
+
+    for (sound, mut sink) in spatial_sinks {
+        sink.set_volume(volume(sound));
+    }
+}
@@ -0,0 +1,542 @@
+use bevy::color::palettes::css::*;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::child_rel::{DuckChildren, Following};
+use crate::ducks::{Boid, Duck, Duckling, TargetPosition};
+use crate::growth::Growth;
+use crate::lake::ReedBed;
+use crate::math::GameRng;
+use crate::navigation::NavigationSystems;
+use crate::perception::{Perception, PerceptionSystems, Sense, Sound};
+use crate::predators::{DETECT_RADIUS, Predator};
+use crate::quacks::QuackKind;
+use crate::text_bubble::Quack;
+use crate::weather::Weather;
+
+pub fn behaviour_plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (
+            choose_activities.after(PerceptionSystems),
+            (
+                wander,
+                adults_search_for_missing_children,
+                adults_call_for_missing_children,
+                adults_defend_broods,
+                adults_seek_shelter,
+                adults_rest,
+                ducklings_seek_parents,
+                ducklings_panic,
+            )
+                .chain(),
+        )
+            .chain()
+            .in_set(BehaviourSystems)
+            .before(NavigationSystems),
+    );
+
+    app.insert_resource(load_table());
+}
+
+/// Sets where ducks want to go, before anything steers them there.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BehaviourSystems;
+
+/// Duck AI tuning for a single kind of weather. See `assets/behaviour.ron`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DuckBehaviour {
+    /// How close an adult has to be before a duckling notices it.
+    pub perception_radius: f32,
+    /// Per-tick chance of nudging the target position a short distance.
+    pub wander_chance: f32,
+    pub wander_distance: f32,
+    /// Per-tick chance of picking a new spot anywhere within `roam_radius`.
+    pub roam_chance: f32,
+    pub roam_radius: f32,
+    /// Adults make for the nearest reed bed.
+    pub seek_shelter: bool,
+    /// Scales how hard ducklings push away from each other.
+    pub duckling_separation: f32,
+    /// Per-tick chance of a duckling quacking for no reason.
+    pub duckling_quack_chance: f32,
+}
+
+/// One row of [`DuckBehaviour`] per [`Weather`].
+#[derive(Resource, Debug, Clone, Deserialize)]
+pub struct DuckBehaviourTable {
+    pub clear: DuckBehaviour,
+    pub fog: DuckBehaviour,
+    pub thunderstorm: DuckBehaviour,
+}
+
+impl DuckBehaviourTable {
+    pub fn get(&self, weather: Weather) -> &DuckBehaviour {
+        match weather {
+            Weather::Clear => &self.clear,
+            Weather::Fog => &self.fog,
+            Weather::Thunderstorm => &self.thunderstorm,
+        }
+    }
+}
+
+/// Baked into the binary so there's no file to go missing.
+const TABLE: &str = include_str!("../assets/behaviour.ron");
+
+/// The ducks can't do anything without a table, so a bad one is fatal.
+fn load_table() -> DuckBehaviourTable {
+    ron::from_str(TABLE).unwrap_or_else(|e| panic!("Failed to parse duck behaviour: {}", e))
+}
+
+/// The [`DuckBehaviour`] row for whatever the weather is doing right now.
+#[derive(SystemParam)]
+pub struct CurrentBehaviour<'w> {
+    table: Res<'w, DuckBehaviourTable>,
+    weather: Res<'w, State<Weather>>,
+}
+
+impl CurrentBehaviour<'_> {
+    pub fn get(&self) -> &DuckBehaviour {
+        self.table.get(**self.weather)
+    }
+}
+
+/// Lower scores than this lose to whatever the duck is already doing, so it
+/// doesn't flicker between two close options.
+const INERTIA: f32 = 0.05;
+
+/// How long an adult searches before stopping to call, and calls before
+/// searching again, in seconds.
+const SEARCH_TIME: f32 = 10.0;
+const CALL_TIME: f32 = 3.0;
+
+/// Adults search this far from where they are for missing children.
+const SEARCH_RADIUS: f32 = 25.0;
+
+/// Seconds a child has to be missing for its parent to be fully agitated.
+const AGITATION_TIME: f32 = 60.0;
+
+/// Seconds a splash stays interesting to an exploring duckling.
+const SPLASH_INTEREST: f32 = 1.0;
+
+/// Energy an adult spends per second on the go, and recovers resting.
+const ENERGY_DRAIN: f32 = 0.01;
+const ENERGY_RECOVERY: f32 = 0.1;
+
+/// What a duck is up to. Picked every tick by scoring each option against
+/// the duck's situation, then carried out by the matching systems below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    // adults
+    Foraging,
+    Searching,
+    Calling,
+    Guarding,
+    Sheltering,
+    Resting,
+    // ducklings
+    SeekingParent,
+    Following,
+    Panicking,
+    Exploring,
+}
+
+impl Activity {
+    pub const ADULT: [Activity; 6] = [
+        Activity::Foraging,
+        Activity::Searching,
+        Activity::Calling,
+        Activity::Guarding,
+        Activity::Sheltering,
+        Activity::Resting,
+    ];
+
+    pub const DUCKLING: [Activity; 4] = [
+        Activity::SeekingParent,
+        Activity::Following,
+        Activity::Panicking,
+        Activity::Exploring,
+    ];
+
+    /// For the debug overlay.
+    pub fn color(self) -> Srgba {
+        match self {
+            Activity::Foraging => GREEN,
+            Activity::Searching => ORANGE,
+            Activity::Calling => YELLOW,
+            Activity::Guarding => RED,
+            Activity::Sheltering => STEEL_BLUE,
+            Activity::Resting => GRAY,
+            Activity::SeekingParent => MAGENTA,
+            Activity::Following => TEAL,
+            Activity::Panicking => CRIMSON,
+            Activity::Exploring => LIME,
+        }
+    }
+}
+
+/// AI state for a duck that isn't the player.
+#[derive(Component, Debug)]
+pub struct Mind {
+    pub activity: Activity,
+    /// Seconds spent on the current activity.
+    pub time: f32,
+    /// Adults tire, and rest when it runs low. In [0, 1].
+    pub energy: f32,
+    /// The nearest predator threatening the duck, or an adult's children.
+    pub threat: Option<Vec3>,
+    /// Seconds an adult has had at least one child missing.
+    pub missing_for: f32,
+    /// Where an adult last saw a missing child.
+    last_known: Option<Vec3>,
+    /// Where a lost duckling last heard an adult calling, if it's not there yet.
+    heard_call: Option<Vec3>,
+}
+
+impl Mind {
+    pub fn new(is_child: bool) -> Self {
+        Self {
+            activity: if is_child {
+                Activity::Exploring
+            } else {
+                Activity::Foraging
+            },
+            time: 0.0,
+            energy: 1.0,
+            threat: None,
+            missing_for: 0.0,
+            last_known: None,
+            heard_call: None,
+        }
+    }
+
+    /// How worked up an adult is about its missing children. In [0, 1].
+    pub fn agitation(&self) -> f32 {
+        (self.missing_for / AGITATION_TIME).min(1.0)
+    }
+
+    /// Scores `activity` for an adult.
+    fn adult_utility(&self, activity: Activity, storm: bool, missing: usize) -> f32 {
+        match activity {
+            Activity::Guarding if self.threat.is_some() => 1.0,
+            Activity::Sheltering if storm => 0.9,
+            Activity::Searching if missing > 0 => 0.5,
+            Activity::Calling if missing > 0 => match self.activity {
+                Activity::Searching if self.time > SEARCH_TIME => 0.6,
+                Activity::Calling if self.time > CALL_TIME => 0.0,
+                _ => 0.4,
+            },
+            // once down, stay down until rested
+            Activity::Resting if self.activity == Activity::Resting && self.energy < 1.0 => 0.6,
+            Activity::Resting => (1.0 - self.energy).powi(2),
+            Activity::Foraging => 0.3,
+            _ => 0.0,
+        }
+    }
+
+    /// Scores `activity` for a duckling.
+    fn duckling_utility(
+        &self,
+        activity: Activity,
+        following: bool,
+        adult_in_sight: bool,
+        independence: f32,
+    ) -> f32 {
+        match activity {
+            Activity::Panicking if self.threat.is_some() => 1.0,
+            Activity::Following if following => 0.6,
+            Activity::SeekingParent if !following && self.heard_call.is_some() => 0.55,
+            Activity::SeekingParent if !following && adult_in_sight => 0.5,
+            // older ducklings stray from whoever they're following
+            Activity::Exploring => 0.2 + 0.45 * independence,
+            _ => 0.0,
+        }
+    }
+
+    fn choose(&mut self, options: &[Activity], utility: impl Fn(&Self, Activity) -> f32) {
+        let best = options
+            .iter()
+            .map(|a| {
+                let bonus = if *a == self.activity { INERTIA } else { 0.0 };
+                (*a, utility(self, *a) + bonus)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(a, _)| a)
+            .unwrap_or(self.activity);
+
+        if best != self.activity {
+            self.activity = best;
+            self.time = 0.0;
+        }
+    }
+}
+
+/// The nearest hunting predator the duck knows of within striking distance
+/// of any of `around`.
+fn remembered_threat(
+    perception: &Perception,
+    around: &[Vec3],
+    predators: &Query<&Predator>,
+) -> Option<Vec3> {
+    perception
+        .percepts()
+        .filter(|p| {
+            p.source
+                .and_then(|e| predators.get(e).ok())
+                .is_some_and(|p| p.is_hunting())
+        })
+        .map(|p| p.position)
+        .filter(|p| around.iter().any(|a| a.distance(*p) < DETECT_RADIUS))
+        .min_by(|a, b| a.distance(around[0]).total_cmp(&b.distance(around[0])))
+}
+
+fn choose_activities(
+    behaviour: CurrentBehaviour,
+    time: Res<Time<Fixed>>,
+    mut minds: Query<(
+        Entity,
+        &Transform,
+        &mut Mind,
+        &Perception,
+        Has<Duckling>,
+        Has<Following>,
+        Option<&Growth>,
+    )>,
+    children: Query<&DuckChildren>,
+    following: Query<&Following>,
+    adults: Query<(), (With<Duck>, Without<Duckling>)>,
+    predators: Query<&Predator>,
+) {
+    let dt = time.delta_secs();
+    let storm = behaviour.get().seek_shelter;
+
+    for (e, tf, mut mind, perception, is_duckling, is_following, growth) in &mut minds {
+        mind.time += dt;
+
+        if is_duckling {
+            mind.threat = remembered_threat(perception, &[tf.translation], &predators);
+            mind.heard_call = perception
+                .last_heard(Sound::Quack(QuackKind::Calling))
+                .map(|p| p.position)
+                .filter(|p| !is_following && p.distance(tf.translation) > 2.0);
+            let adult_in_sight = perception
+                .percepts()
+                .any(|p| p.sense == Sense::Sight && p.source.is_some_and(|s| adults.contains(s)));
+            mind.choose(&Activity::DUCKLING, |m, a| {
+                let independence = growth.map(|g| g.independence).unwrap_or(0.0);
+                m.duckling_utility(a, is_following, adult_in_sight, independence)
+            });
+            continue;
+        }
+
+        let brood: Vec<Entity> = children
+            .get(e)
+            .map(|c| c.iter().collect())
+            .unwrap_or_default();
+        let missing: Vec<Entity> = brood
+            .iter()
+            .copied()
+            .filter(|c| following.get(*c).map(|f| f.0).ok() != Some(e))
+            .collect();
+
+        if missing.is_empty() {
+            mind.missing_for = 0.0;
+            mind.last_known = None;
+        } else {
+            mind.missing_for += dt;
+            let freshest = missing
+                .iter()
+                .filter_map(|c| perception.recall(*c))
+                .min_by(|a, b| a.age.total_cmp(&b.age));
+            if let Some(p) = freshest {
+                mind.last_known = Some(p.position);
+            }
+        }
+
+        // the adult itself, and wherever it knows its children to be
+        let around: Vec<Vec3> = std::iter::once(tf.translation)
+            .chain(
+                brood
+                    .iter()
+                    .filter_map(|c| perception.recall(*c))
+                    .map(|p| p.position),
+            )
+            .collect();
+        mind.threat = remembered_threat(perception, &around, &predators);
+
+        mind.energy = if mind.activity == Activity::Resting {
+            (mind.energy + ENERGY_RECOVERY * dt).min(1.0)
+        } else {
+            (mind.energy - ENERGY_DRAIN * dt).max(0.0)
+        };
+
+        let storm = storm || perception.last_heard(Sound::Thunder).is_some();
+        let missing = missing.len();
+        mind.choose(&Activity::ADULT, |m, a| m.adult_utility(a, storm, missing));
+    }
+}
+
+/// Foraging adults and exploring ducklings potter about. Ducklings paddle
+/// over to see what's splashing.
+fn wander(
+    mut rng: ResMut<GameRng>,
+    behaviour: CurrentBehaviour,
+    targets: Query<(&mut TargetPosition, &Mind, &Perception)>,
+) {
+    let behaviour = behaviour.get();
+    for (mut target, mind, perception) in targets {
+        if !matches!(mind.activity, Activity::Foraging | Activity::Exploring) {
+            continue;
+        }
+        let splash = perception.last_heard(Sound::Splash);
+        if mind.activity == Activity::Exploring
+            && let Some(splash) = splash.filter(|p| p.age < SPLASH_INTEREST)
+        {
+            target.pos = splash.position.with_y(0.0);
+        } else if rng.chance(behaviour.wander_chance) {
+            let delta = rng.vec(0.1, behaviour.wander_distance);
+            target.pos.x += delta.x;
+            target.pos.z += delta.y;
+        } else if rng.chance(behaviour.roam_chance) {
+            let pos = rng.vec(0.0, behaviour.roam_radius);
+            target.pos.x = pos.x;
+            target.pos.z = pos.y;
+        }
+    }
+}
+
+/// Adults head for where they last saw a missing child, then cast about
+/// around it.
+fn adults_search_for_missing_children(
+    mut rng: ResMut<GameRng>,
+    adults: Query<(&Transform, &mut TargetPosition, &Mind), Without<Duckling>>,
+) {
+    for (tf, mut target, mind) in adults {
+        if mind.activity != Activity::Searching {
+            continue;
+        }
+        let centre = mind.last_known.unwrap_or(tf.translation).with_y(0.0);
+        if mind.time == 0.0 {
+            target.pos = centre;
+        } else if target.pos.distance(tf.translation.with_y(0.0)) < 2.0 {
+            let delta = rng.vec(SEARCH_RADIUS * 0.3, SEARCH_RADIUS);
+            target.pos = centre + Vec3::new(delta.x, 0.0, delta.y);
+        }
+    }
+}
+
+fn adults_call_for_missing_children(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    adults: Query<(Entity, &Transform, &mut TargetPosition, &Mind), Without<Duckling>>,
+) {
+    for (adult, tf, mut target, mind) in adults {
+        if mind.activity != Activity::Calling {
+            continue;
+        }
+        // stop and call, more often the longer they've been gone
+        target.pos = tf.translation.with_y(0.0);
+        if mind.time == 0.0 || rng.chance(0.01 + 0.04 * mind.agitation()) {
+            commands.write_message(Quack::new(adult, QuackKind::Calling));
+        }
+    }
+}
+
+/// Parents head for predators that threaten them or their children.
+fn adults_defend_broods(adults: Query<(&mut TargetPosition, &Mind), Without<Duckling>>) {
+    for (mut target, mind) in adults {
+        if let (Activity::Guarding, Some(threat)) = (mind.activity, mind.threat) {
+            target.pos = threat.with_y(0.0);
+        }
+    }
+}
+
+fn adults_seek_shelter(
+    adults: Query<(&Transform, &mut TargetPosition, &Mind), Without<Duckling>>,
+    reeds: Query<&Transform, With<ReedBed>>,
+) {
+    for (tf, mut target, mind) in adults {
+        if mind.activity != Activity::Sheltering {
+            continue;
+        }
+
+        let nearest = reeds.iter().min_by(|a, b| {
+            let da = a.translation.distance_squared(tf.translation);
+            let db = b.translation.distance_squared(tf.translation);
+            da.total_cmp(&db)
+        });
+
+        if let Some(reed) = nearest {
+            target.pos = reed.translation.with_y(0.0);
+        }
+    }
+}
+
+fn adults_rest(adults: Query<(&Transform, &mut TargetPosition, &Mind), Without<Duckling>>) {
+    for (tf, mut target, mind) in adults {
+        if mind.activity == Activity::Resting && mind.time == 0.0 {
+            target.pos = tf.translation.with_y(0.0);
+        }
+    }
+}
+
+/// Lost ducklings make for the last call they heard, or else the nearest
+/// adult they can see.
+fn ducklings_seek_parents(
+    ducklings: Query<(&Transform, &mut TargetPosition, &Mind, &Perception), With<Duckling>>,
+    adults: Query<(), (With<Duck>, Without<Duckling>)>,
+) {
+    for (tf, mut target, mind, perception) in ducklings {
+        if mind.activity != Activity::SeekingParent {
+            continue;
+        }
+        if let Some(call) = mind.heard_call {
+            target.pos = call.with_y(0.0);
+            continue;
+        }
+        let nearest = perception
+            .percepts()
+            .filter(|p| p.sense == Sense::Sight && p.source.is_some_and(|s| adults.contains(s)))
+            .map(|p| p.position)
+            .min_by(|a, b| {
+                a.distance(tf.translation)
+                    .total_cmp(&b.distance(tf.translation))
+            });
+        if let Some(p) = nearest {
+            target.pos = p.with_y(0.0);
+        }
+    }
+}
+
+/// Panicking ducklings swim away from the threat, quacking.
+fn ducklings_panic(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    ducklings: Query<(Entity, &Transform, &mut Boid, &Mind), With<Duckling>>,
+) {
+    for (e, tf, mut boid, mind) in ducklings {
+        boid.flee = Vec3::ZERO;
+        let (Activity::Panicking, Some(threat)) = (mind.activity, mind.threat) else {
+            continue;
+        };
+
+        let delta = (tf.translation - threat).with_y(0.0);
+        boid.flee = delta.normalize_or_zero() * (DETECT_RADIUS - delta.length()).max(0.0);
+        if rng.chance(0.02) {
+            commands.write_message(Quack::new(e, QuackKind::Panicked));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn behaviour_table_parses() {
+        let table = load_table();
+        assert!(table.thunderstorm.seek_shelter);
+        assert!(!table.clear.seek_shelter);
+    }
+}
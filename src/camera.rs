@@ -0,0 +1,372 @@
+use bevy::input::mouse::{AccumulatedMouseMotion, MouseWheel};
+use bevy::prelude::*;
+use std::f32::consts::{PI, TAU};
+
+use crate::child_rel::FollowedBy;
+use crate::detached_camera::{FreeFlyCamera, move_camera};
+use crate::ducks::Duck;
+use crate::hud::Notification;
+use crate::math::{smoothing, spring_damp};
+use crate::photo::PhotoState;
+use crate::player::PlayerDuck;
+
+pub fn camera_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            handle_camera_mode_keys.run_if(in_state(PhotoState::Off)),
+            blend_on_mode_change,
+            update_camera_scale,
+            orbit_camera_with_mouse.run_if(resource_equals(CameraMode::Orbit)),
+            move_camera.run_if(resource_equals(CameraMode::FreeFly)),
+            update_chase_rig,
+            position_camera,
+        )
+            .chain(),
+    );
+
+    app.insert_resource(CameraMode::Chase);
+    app.insert_resource(CameraBlend {
+        from: Transform::default(),
+        t: 1.0,
+    });
+    app.insert_resource(CameraScale {
+        target: 0.0,
+        actual: 1.0,
+    });
+    app.insert_resource(ChaseRig::default());
+    app.insert_resource(OrbitAngles {
+        yaw: 0.0,
+        pitch: 0.4,
+    });
+    app.insert_resource(FreeFlyCamera::default());
+    app.insert_resource(CinematicShot {
+        position: Vec3::ZERO,
+        focus: Vec3::ZERO,
+        cut: false,
+    });
+}
+
+/// Localization keys used here, besides each mode's [`CameraMode::key`].
+pub const LOCALE_KEYS: &[&str] = &["notify.camera"];
+
+/// Seconds to blend from one camera mode to the next.
+const BLEND_TIME: f32 = 0.8;
+
+/// The camera is kept at least this far above the water.
+const MIN_CAMERA_HEIGHT: f32 = 0.5;
+
+/// Radians per pixel of mouse drag.
+const ORBIT_SENSITIVITY: f32 = 0.005;
+
+/// Stiffness of the spring dragging the chase camera after the player.
+const CHASE_SPRING: f32 = 5.0;
+
+/// How quickly the chase camera swings round behind the player, per second.
+const CHASE_TURN_RATE: f32 = 2.5;
+
+/// The chase camera looks this many seconds ahead of where the player is
+/// heading.
+const LOOK_AHEAD_TIME: f32 = 0.6;
+
+/// Followers spread further than this from the player make the chase
+/// camera pull back to fit them in, all the way at [`FRAMING_MAX_SPREAD`].
+const FRAMING_MIN_SPREAD: f32 = 4.0;
+const FRAMING_MAX_SPREAD: f32 = 40.0;
+
+/// How quickly zoom and framing follow their targets, per second.
+const ZOOM_RATE: f32 = 3.0;
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Behind the player, looking ahead.
+    Chase,
+    /// Around the player, steered by dragging the mouse.
+    Orbit,
+    /// High above the player, north up.
+    TopDown,
+    /// Detached from the player, for debugging.
+    FreeFly,
+    /// Wherever the [`CinematicShot`] says.
+    Cinematic,
+}
+
+impl CameraMode {
+    pub const ALL: [CameraMode; 5] = [
+        CameraMode::Chase,
+        CameraMode::Orbit,
+        CameraMode::TopDown,
+        CameraMode::FreeFly,
+        CameraMode::Cinematic,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            CameraMode::Chase => "camera.chase",
+            CameraMode::Orbit => "camera.orbit",
+            CameraMode::TopDown => "camera.top_down",
+            CameraMode::FreeFly => "camera.free_fly",
+            CameraMode::Cinematic => "camera.cinematic",
+        }
+    }
+}
+
+/// A shot set up by the director for [`CameraMode::Cinematic`].
+#[derive(Resource, Debug)]
+pub struct CinematicShot {
+    pub position: Vec3,
+    pub focus: Vec3,
+    /// Cut straight to the shot instead of blending.
+    pub cut: bool,
+}
+
+/// The camera's transform when the mode last changed, and how far it has
+/// blended from there to the new mode, in [0, 1].
+#[derive(Resource, Debug)]
+struct CameraBlend {
+    from: Transform,
+    t: f32,
+}
+
+/// Zoom, in [0, 1], from close up to far away.
+#[derive(Resource)]
+struct CameraScale {
+    target: f32,
+    actual: f32,
+}
+
+/// A smoothed stand-in for the player that the chase camera follows, so
+/// that the duck's bobbing and sudden turns don't shake the view.
+#[derive(Resource, Debug, Default)]
+struct ChaseRig {
+    position: Vec3,
+    velocity: Vec3,
+    yaw: f32,
+    look_ahead: Vec3,
+    /// Zoom needed to fit the player's followers in, in [0, 1].
+    framing: f32,
+}
+
+#[derive(Resource, Debug)]
+struct OrbitAngles {
+    yaw: f32,
+    pitch: f32,
+}
+
+fn handle_camera_mode_keys(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut mode: ResMut<CameraMode>,
+) {
+    let next = if keys.just_pressed(KeyCode::KeyC) {
+        match *mode {
+            CameraMode::Chase => CameraMode::Orbit,
+            CameraMode::Orbit => CameraMode::TopDown,
+            CameraMode::TopDown | CameraMode::FreeFly | CameraMode::Cinematic => CameraMode::Chase,
+        }
+    } else if keys.just_pressed(KeyCode::F4) {
+        match *mode {
+            CameraMode::FreeFly => CameraMode::Chase,
+            _ => CameraMode::FreeFly,
+        }
+    } else {
+        return;
+    };
+
+    *mode = next;
+    commands.trigger(Notification {
+        key: "notify.camera",
+        detail: Some(next.key()),
+    });
+}
+
+fn blend_on_mode_change(
+    mode: Res<CameraMode>,
+    shot: Res<CinematicShot>,
+    mut blend: ResMut<CameraBlend>,
+    mut free: ResMut<FreeFlyCamera>,
+    camera: Single<&Transform, With<Camera3d>>,
+) {
+    if !mode.is_changed() || mode.is_added() {
+        return;
+    }
+
+    let cut = *mode == CameraMode::Cinematic && shot.cut;
+    *blend = CameraBlend {
+        from: **camera,
+        t: if cut { 1.0 } else { 0.0 },
+    };
+    free.0 = **camera;
+}
+
+fn update_camera_scale(
+    mut events: MessageReader<MouseWheel>,
+    mut scale: ResMut<CameraScale>,
+    time: Res<Time>,
+) {
+    let mut delta = 0.0;
+
+    let rate = 0.06;
+
+    for event in events.read() {
+        if event.y < 0.0 {
+            delta += rate;
+        } else {
+            delta -= rate;
+        }
+    }
+
+    scale.target += delta;
+
+    scale.target = scale.target.clamp(0.0, 1.0);
+
+    scale.actual += (scale.target - scale.actual) * smoothing(ZOOM_RATE, time.delta_secs());
+}
+
+fn orbit_camera_with_mouse(
+    mouse: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
+    mut orbit: ResMut<OrbitAngles>,
+) {
+    if mouse.pressed(MouseButton::Left) {
+        orbit.yaw -= motion.delta.x * ORBIT_SENSITIVITY;
+        orbit.pitch = (orbit.pitch + motion.delta.y * ORBIT_SENSITIVITY).clamp(0.05, 1.5);
+    }
+}
+
+fn update_chase_rig(
+    time: Res<Time>,
+    mut rig: ResMut<ChaseRig>,
+    player: Single<(&Transform, &Duck, Option<&FollowedBy>), With<PlayerDuck>>,
+    followers: Query<&Transform, Without<PlayerDuck>>,
+) {
+    let (tf, duck, followed_by) = *player;
+    let dt = time.delta_secs();
+
+    // snap into place the first time, rather than swooping in from the origin
+    if rig.position == Vec3::ZERO {
+        rig.position = tf.translation;
+    }
+
+    let ChaseRig {
+        position, velocity, ..
+    } = &mut *rig;
+    spring_damp(position, velocity, tf.translation, CHASE_SPRING, dt);
+
+    let forward = tf.local_z();
+    let target_yaw = forward.x.atan2(forward.z);
+    let diff = (target_yaw - rig.yaw + PI).rem_euclid(TAU) - PI;
+    rig.yaw += diff * smoothing(CHASE_TURN_RATE, dt);
+
+    let look_ahead = Vec3::new(duck.velocity.x, 0.0, duck.velocity.z) * LOOK_AHEAD_TIME;
+    rig.look_ahead = rig
+        .look_ahead
+        .lerp(look_ahead, smoothing(CHASE_TURN_RATE, dt));
+
+    let spread = followed_by
+        .into_iter()
+        .flat_map(|f| followers.iter_many(f.iter()))
+        .map(|f| f.translation.distance(tf.translation))
+        .fold(0.0, f32::max);
+    let framing =
+        ((spread - FRAMING_MIN_SPREAD) / (FRAMING_MAX_SPREAD - FRAMING_MIN_SPREAD)).clamp(0.0, 1.0);
+    rig.framing += (framing - rig.framing) * smoothing(ZOOM_RATE, dt);
+}
+
+fn chase_transform(rig: &ChaseRig, scale: f32) -> (Transform, Vec3) {
+    let player =
+        Transform::from_translation(rig.position).with_rotation(Quat::from_rotation_y(rig.yaw));
+    let scale = scale.max(rig.framing);
+
+    let c1 = Vec3::new(2.0, 2.0, -5.0);
+    let c2 = Vec3::new(0.0, 30.0, -15.0);
+
+    let f1 = Vec3::new(0.0, 0.0, 10.0);
+    let f2 = Vec3::new(0.0, 0.0, 15.0);
+
+    let c = c1.lerp(c2, scale);
+    let f = f1.lerp(f2, scale);
+
+    let mut camera = player * Transform::from_translation(c);
+    let focus_transform = Transform::from_translation(f);
+    let focus = (player * focus_transform).translation + rig.look_ahead;
+
+    camera.look_at(focus, Vec3::Y);
+
+    (camera, focus)
+}
+
+fn orbit_transform(focus: Vec3, orbit: &OrbitAngles, scale: f32) -> Transform {
+    let distance = 6.0f32.lerp(40.0, scale);
+    let offset = Quat::from_euler(EulerRot::YXZ, orbit.yaw, -orbit.pitch, 0.0) * Vec3::Z;
+    Transform::from_translation(focus + offset * distance).looking_at(focus, Vec3::Y)
+}
+
+fn top_down_transform(focus: Vec3, scale: f32) -> Transform {
+    let height = 30.0f32.lerp(120.0, scale);
+    Transform::from_translation(focus + Vec3::Y * height).looking_at(focus, Vec3::NEG_Z)
+}
+
+/// Pulls a camera that would dip under the water back up along its line of
+/// sight, so it keeps looking at the same point.
+fn keep_above_water(mut camera: Transform, focus: Option<Vec3>) -> Transform {
+    if camera.translation.y >= MIN_CAMERA_HEIGHT {
+        return camera;
+    }
+
+    match focus {
+        Some(focus) if focus.y > MIN_CAMERA_HEIGHT => {
+            let t = (focus.y - MIN_CAMERA_HEIGHT) / (focus.y - camera.translation.y);
+            camera.translation = focus.lerp(camera.translation, t);
+        }
+        _ => camera.translation.y = MIN_CAMERA_HEIGHT,
+    }
+    if let Some(focus) = focus {
+        camera.look_at(focus, Vec3::Y);
+    }
+    camera
+}
+
+fn position_camera(
+    real_time: Res<Time<Real>>,
+    mode: Res<CameraMode>,
+    shot: Res<CinematicShot>,
+    scale: Res<CameraScale>,
+    orbit: Res<OrbitAngles>,
+    rig: Res<ChaseRig>,
+    mut free: ResMut<FreeFlyCamera>,
+    mut blend: ResMut<CameraBlend>,
+    player: Single<&Transform, With<PlayerDuck>>,
+    mut camera: Single<&mut Transform, (With<Camera3d>, Without<PlayerDuck>)>,
+) {
+    let (target, focus) = match *mode {
+        CameraMode::Chase => {
+            let (tf, focus) = chase_transform(&rig, scale.actual);
+            (tf, Some(focus))
+        }
+        CameraMode::Orbit => (
+            orbit_transform(player.translation, &orbit, scale.actual),
+            Some(player.translation),
+        ),
+        CameraMode::TopDown => (
+            top_down_transform(player.translation, scale.actual),
+            Some(player.translation),
+        ),
+        CameraMode::FreeFly => {
+            free.0 = keep_above_water(free.0, None);
+            (free.0, None)
+        }
+        CameraMode::Cinematic => (
+            Transform::from_translation(shot.position).looking_at(shot.focus, Vec3::Y),
+            Some(shot.focus),
+        ),
+    };
+    let target = keep_above_water(target, focus);
+
+    // in real time, so blends keep their pace in slow motion
+    blend.t = (blend.t + real_time.delta_secs() / BLEND_TIME).min(1.0);
+    let s = blend.t * blend.t * (3.0 - 2.0 * blend.t);
+
+    camera.translation = blend.from.translation.lerp(target.translation, s);
+    camera.rotation = blend.from.rotation.slerp(target.rotation, s);
+}
@@ -1,18 +1,45 @@
 use bevy::color::palettes::tailwind::*;
+use bevy::diagnostic::{
+    Diagnostic, DiagnosticPath, Diagnostics, DiagnosticsStore, FrameTimeDiagnosticsPlugin,
+    RegisterDiagnostic,
+};
+use bevy::platform::time::Instant;
 use bevy::prelude::*;
 use noiz::prelude::*;
 
-use crate::math::random_range;
+use crate::debug::is_debug_enabled;
+use crate::lake::LAKE_RADIUS;
+use crate::math::cosmetic_random_range;
 
+/// Expects [`FrameTimeDiagnosticsPlugin`] to have been added, for comparing
+/// the renderers.
 pub fn clouds_plugin(app: &mut App) {
-    app.add_systems(Startup, add_clouds);
+    app.register_diagnostic(Diagnostic::new(CLOUD_UPDATE_TIME).with_suffix("ms"));
+
+    app.add_systems(
+        Startup,
+        (setup_cloud_assets, add_clouds, add_cloud_shadows).chain(),
+    );
+
+    app.add_systems(
+        Update,
+        (
+            record_cloud_frame_times,
+            toggle_cloud_renderer_on_f3.run_if(is_debug_enabled),
+        )
+            .chain(),
+    );
 
     app.add_systems(
         FixedUpdate,
         (
             update_noise_sample_offset,
-            sample_cloud_size,
+            update_cloud_lods.run_if(resource_equals(CloudRenderer::Clustered)),
+            sample_cloud_size.run_if(resource_equals(CloudRenderer::Clustered)),
+            sample_legacy_cloud_size.run_if(resource_equals(CloudRenderer::Legacy)),
+            sample_cloud_shadows,
             update_lpf_cloud_speed,
+            update_lpf_cloud_cover,
             update_cloud_material,
         )
             .chain(),
@@ -22,6 +49,8 @@ pub fn clouds_plugin(app: &mut App) {
 
     app.add_observer(on_set_cloud_color);
 
+    app.add_observer(on_set_cloud_cover);
+
     let noise = Noise::<MixCellGradients<OrthoGrid, Smoothstep, QuickGradients>>::default();
 
     app.insert_resource(NoiseFunc(noise));
@@ -31,19 +60,35 @@ pub fn clouds_plugin(app: &mut App) {
         actual: 120.0,
     });
     app.insert_resource(CloudColor(Srgba::RED.into()));
+    app.insert_resource(CloudNoiseSettings::default());
+    app.insert_resource(CloudFrameTimes::default());
+    app.insert_resource(CloudRenderer::Clustered);
 }
 
-const NUM_CLOUDS: usize = 3000;
+const NUM_CLOUD_CLUSTERS: usize = 1200;
+const NUM_CLOUD_SHAPES: usize = 8;
+const NUM_LEGACY_CLOUDS: usize = 3000;
 const CLOUD_RANGE: f32 = 10000.0;
 const CLOUD_HEIGHT: f32 = 900.0;
 const CLOUD_MAX_RADIUS: f32 = 800.0;
 const CLOUD_NOISE_SCALE: f32 = 2000.0;
 
+/// Beyond this distance from the camera, clusters switch to the low-poly mesh.
+const CLOUD_LOD_FAR_DISTANCE: f32 = 2500.0;
+/// Beyond this distance from the camera, clusters are hidden and not updated.
+const CLOUD_LOD_CULL_DISTANCE: f32 = 7000.0;
+
+const CLOUD_SHADOW_CELLS: i32 = 16;
+const CLOUD_SHADOW_HEIGHT: f32 = 0.04;
+
+/// CPU time spent resizing clouds from noise each fixed tick.
+pub const CLOUD_UPDATE_TIME: DiagnosticPath = DiagnosticPath::const_new("clouds/update_time");
+
 #[derive(Resource, Deref, DerefMut)]
 struct NoiseFunc(Noise<MixCellGradients<OrthoGrid, Smoothstep, QuickGradients>>);
 
 #[derive(Resource, Deref, DerefMut)]
-struct NoiseOffset(Vec2);
+pub struct NoiseOffset(pub Vec2);
 
 #[derive(Resource, Debug)]
 struct CloudSpeed {
@@ -54,9 +99,114 @@ struct CloudSpeed {
 #[derive(Resource, Debug)]
 struct CloudColor(Color);
 
+/// Shape of the fractal noise the cloud deck is sampled from.
+///
+/// `coverage` is the fraction of sky with any cloud at all, and `density`
+/// scales how big those clouds get. Both ease towards their targets, which
+/// are set by the weather via [`SetCloudCover`].
+#[derive(Resource, Debug, Clone)]
+pub struct CloudNoiseSettings {
+    pub octaves: u32,
+    pub lacunarity: f32,
+    pub persistence: f32,
+    pub coverage: f32,
+    pub target_coverage: f32,
+    pub density: f32,
+    pub target_density: f32,
+}
+
+impl Default for CloudNoiseSettings {
+    fn default() -> Self {
+        Self {
+            octaves: 4,
+            lacunarity: 2.0,
+            persistence: 0.5,
+            coverage: 0.5,
+            target_coverage: 0.5,
+            density: 1.0,
+            target_density: 1.0,
+        }
+    }
+}
+
+impl CloudNoiseSettings {
+    pub const TUNABLES: [&'static str; 5] = [
+        "octaves",
+        "lacunarity",
+        "persistence",
+        "coverage",
+        "density",
+    ];
+
+    pub fn tunable(&self, index: usize) -> f32 {
+        match index {
+            0 => self.octaves as f32,
+            1 => self.lacunarity,
+            2 => self.persistence,
+            3 => self.target_coverage,
+            4 => self.target_density,
+            _ => 0.0,
+        }
+    }
+
+    /// Steps one of [`Self::TUNABLES`] up or down by a sensible increment.
+    pub fn nudge_tunable(&mut self, index: usize, steps: i32) {
+        let s = steps as f32;
+        match index {
+            0 => self.octaves = (self.octaves as i32 + steps).clamp(1, 8) as u32,
+            1 => self.lacunarity = (self.lacunarity + s * 0.1).clamp(1.0, 4.0),
+            2 => self.persistence = (self.persistence + s * 0.05).clamp(0.05, 1.0),
+            3 => self.target_coverage = (self.target_coverage + s * 0.05).clamp(0.0, 1.0),
+            4 => self.target_density = (self.target_density + s * 0.05).clamp(0.0, 1.0),
+            _ => (),
+        }
+    }
+
+    /// Fractal brownian motion over the base gradient noise, in [-1, 1].
+    fn fbm(&self, noise: &NoiseFunc, p: Vec2) -> f32 {
+        let mut sum = 0.0;
+        let mut norm = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+
+        for octave in 0..self.octaves {
+            // shift each octave so their lattices don't line up at the origin
+            let shift = Vec2::splat(octave as f32 * 17.31);
+            let t: f32 = noise.sample(p * frequency + shift);
+            sum += t * amplitude;
+            norm += amplitude;
+            amplitude *= self.persistence;
+            frequency *= self.lacunarity;
+        }
+
+        if norm > 0.0 { sum / norm } else { 0.0 }
+    }
+}
+
 #[derive(Resource, Debug)]
 struct CloudMaterial(Handle<StandardMaterial>);
 
+/// Which cloud implementation is active. The legacy renderer is kept around
+/// so the two can be compared with F3 while the debug view is open.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloudRenderer {
+    Legacy,
+    Clustered,
+}
+
+struct CloudShape {
+    near: Handle<Mesh>,
+    far: Handle<Mesh>,
+}
+
+#[derive(Resource)]
+struct CloudAssets {
+    shapes: Vec<CloudShape>,
+    legacy_mesh: Handle<Mesh>,
+    shadow_mesh: Handle<Mesh>,
+    shadow_material: Handle<StandardMaterial>,
+}
+
 fn update_cloud_material(
     handle: Res<CloudMaterial>,
     color: Res<CloudColor>,
@@ -72,6 +222,50 @@ fn update_cloud_material(
 #[derive(Component)]
 struct Cloud;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloudLod {
+    Near,
+    Far,
+    Culled,
+}
+
+impl CloudLod {
+    fn from_distance(d: f32) -> Self {
+        if d > CLOUD_LOD_CULL_DISTANCE {
+            CloudLod::Culled
+        } else if d > CLOUD_LOD_FAR_DISTANCE {
+            CloudLod::Far
+        } else {
+            CloudLod::Near
+        }
+    }
+
+    /// How many fixed ticks pass between noise samples at this LOD.
+    fn update_interval(&self) -> Option<u32> {
+        match self {
+            CloudLod::Near => Some(1),
+            CloudLod::Far => Some(10),
+            CloudLod::Culled => None,
+        }
+    }
+}
+
+/// A single cloud made of several merged puffs. Clusters sharing a shape
+/// share a mesh, so they are drawn as one instanced batch.
+#[derive(Component, Debug)]
+struct CloudCluster {
+    index: u32,
+    shape: usize,
+    lod: CloudLod,
+}
+
+/// A patch of shade on the lake, sized by the cloud density directly
+/// above it along the sun direction.
+#[derive(Component, Debug)]
+struct CloudShadow {
+    ground: Vec2,
+}
+
 #[derive(Event, Debug)]
 pub struct SetWindSpeed(pub f32);
 
@@ -83,57 +277,391 @@ fn on_set_wind_speed(event: On<SetWindSpeed>, mut speed: ResMut<CloudSpeed>) {
 #[derive(Event, Debug)]
 pub struct SetCloudColor(pub Color);
 
+#[derive(Event, Debug)]
+pub struct SetCloudCover {
+    pub coverage: f32,
+    pub density: f32,
+}
+
+fn on_set_cloud_cover(event: On<SetCloudCover>, mut settings: ResMut<CloudNoiseSettings>) {
+    info!("Set cloud cover: {:?}", event);
+    settings.target_coverage = event.coverage;
+    settings.target_density = event.density;
+}
+
 fn on_set_cloud_color(event: On<SetCloudColor>, mut color: ResMut<CloudColor>) {
     info!("Set cloud color: {:?}", event);
     color.0 = event.0;
 }
 
-fn add_clouds(
+/// Builds one cloud from a handful of overlapping spheres, merged into a
+/// single mesh roughly spanning [-1, 1] on X and Z.
+fn cloud_cluster_mesh(puffs: &[(Vec3, f32)], subdivisions: u32) -> Mesh {
+    let mut puffs = puffs.iter().map(|(center, radius)| {
+        Sphere::new(*radius)
+            .mesh()
+            .ico(subdivisions)
+            .unwrap()
+            .translated_by(*center)
+    });
+
+    let mut mesh = puffs.next().unwrap();
+    for puff in puffs {
+        mesh.merge(&puff).unwrap();
+    }
+    mesh
+}
+
+fn random_cloud_puffs() -> Vec<(Vec3, f32)> {
+    let mut puffs = vec![(Vec3::ZERO, cosmetic_random_range(0.6..0.8))];
+    let n = cosmetic_random_range(4..=8);
+    for _ in 0..n {
+        let x = cosmetic_random_range(-0.6..=0.6);
+        let y = cosmetic_random_range(-0.1..=0.25);
+        let z = cosmetic_random_range(-0.6..=0.6);
+        puffs.push((Vec3::new(x, y, z), cosmetic_random_range(0.3..0.55)));
+    }
+    puffs
+}
+
+fn setup_cloud_assets(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    let cloud_mesh = meshes.add(Sphere::new(1.0).mesh().ico(5).unwrap());
+    let shapes = (0..NUM_CLOUD_SHAPES)
+        .map(|_| {
+            let puffs = random_cloud_puffs();
+            CloudShape {
+                near: meshes.add(cloud_cluster_mesh(&puffs, 3)),
+                far: meshes.add(cloud_cluster_mesh(&puffs, 1)),
+            }
+        })
+        .collect();
+
+    let legacy_mesh = meshes.add(Sphere::new(1.0).mesh().ico(5).unwrap());
+
+    let shadow_mesh = meshes.add(Cylinder::new(1.0, 0.01));
+    let shadow_material = materials.add(StandardMaterial {
+        base_color: Color::BLACK.with_alpha(0.2),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
 
     let cloud_material = materials.add(StandardMaterial::from_color(GRAY_600));
 
-    commands.insert_resource(CloudMaterial(cloud_material.clone()));
+    commands.insert_resource(CloudMaterial(cloud_material));
+    commands.insert_resource(CloudAssets {
+        shapes,
+        legacy_mesh,
+        shadow_mesh,
+        shadow_material,
+    });
+}
+
+fn add_clouds(
+    mut commands: Commands,
+    assets: Res<CloudAssets>,
+    material: Res<CloudMaterial>,
+    renderer: Res<CloudRenderer>,
+) {
+    spawn_clouds(&mut commands, &assets, &material, *renderer);
+}
 
-    for _ in 0..NUM_CLOUDS {
-        let x = random_range(-1.0..=1.0) * CLOUD_RANGE;
-        let z = random_range(-1.0..=1.0) * CLOUD_RANGE;
+fn spawn_clouds(
+    commands: &mut Commands,
+    assets: &CloudAssets,
+    material: &CloudMaterial,
+    renderer: CloudRenderer,
+) {
+    match renderer {
+        CloudRenderer::Legacy => add_legacy_clouds(commands, assets, material),
+        CloudRenderer::Clustered => add_cloud_clusters(commands, assets, material),
+    }
+}
+
+fn add_cloud_clusters(commands: &mut Commands, assets: &CloudAssets, material: &CloudMaterial) {
+    for index in 0..NUM_CLOUD_CLUSTERS {
+        let x = cosmetic_random_range(-1.0..=1.0) * CLOUD_RANGE;
+        let z = cosmetic_random_range(-1.0..=1.0) * CLOUD_RANGE;
+        let angle = cosmetic_random_range(0.0..std::f32::consts::PI * 2.0);
+        let shape = cosmetic_random_range(0..NUM_CLOUD_SHAPES);
+
+        let transform = Transform::from_xyz(x, CLOUD_HEIGHT, z)
+            .with_rotation(Quat::from_rotation_y(angle))
+            .with_scale(Vec3::ZERO);
+
+        commands.spawn((
+            transform,
+            Mesh3d(assets.shapes[shape].near.clone()),
+            MeshMaterial3d(material.0.clone()),
+            Visibility::Visible,
+            Cloud,
+            CloudCluster {
+                index: index as u32,
+                shape,
+                lod: CloudLod::Near,
+            },
+        ));
+    }
+}
 
-        // let radius = random_range(10.0..40.0);
+fn add_legacy_clouds(commands: &mut Commands, assets: &CloudAssets, material: &CloudMaterial) {
+    for _ in 0..NUM_LEGACY_CLOUDS {
+        let x = cosmetic_random_range(-1.0..=1.0) * CLOUD_RANGE;
+        let z = cosmetic_random_range(-1.0..=1.0) * CLOUD_RANGE;
 
         let transform = Transform::from_xyz(x, CLOUD_HEIGHT, z);
 
         commands.spawn((
             transform,
-            Mesh3d(cloud_mesh.clone()),
+            Mesh3d(assets.legacy_mesh.clone()),
             Cloud,
-            MeshMaterial3d(cloud_material.clone()),
+            MeshMaterial3d(material.0.clone()),
         ));
     }
 }
 
+fn add_cloud_shadows(mut commands: Commands, assets: Res<CloudAssets>) {
+    let cell = LAKE_RADIUS * 2.0 / CLOUD_SHADOW_CELLS as f32;
+    for i in 0..CLOUD_SHADOW_CELLS {
+        for j in 0..CLOUD_SHADOW_CELLS {
+            let x = -LAKE_RADIUS + (i as f32 + 0.5) * cell;
+            let z = -LAKE_RADIUS + (j as f32 + 0.5) * cell;
+
+            commands.spawn((
+                Transform::from_xyz(x, CLOUD_SHADOW_HEIGHT, z).with_scale(Vec3::ZERO),
+                Mesh3d(assets.shadow_mesh.clone()),
+                MeshMaterial3d(assets.shadow_material.clone()),
+                CloudShadow {
+                    ground: Vec2::new(x, z),
+                },
+            ));
+        }
+    }
+}
+
 fn update_noise_sample_offset(mut offset: ResMut<NoiseOffset>, speed: Res<CloudSpeed>) {
     offset.0 += Vec2::splat(speed.actual / 1000.0);
 }
 
+/// Cloud radius in meters for a cloud centered at the given XZ position.
+fn cloud_radius(noise: &NoiseFunc, settings: &CloudNoiseSettings, offset: Vec2, xz: Vec2) -> f32 {
+    let p = xz / CLOUD_NOISE_SCALE + offset;
+    let t = settings.fbm(noise, p); // within [-1, 1], mostly near 0
+    // shift the whole field up or down: 0 is clear sky, 1 is overcast
+    let t = t + settings.coverage * 2.0 - 1.0;
+    t.clamp(0.0, 1.0) * settings.density * CLOUD_MAX_RADIUS
+}
+
+fn update_cloud_lods(
+    assets: Res<CloudAssets>,
+    camera: Single<&Transform, With<Camera3d>>,
+    clouds: Query<(&Transform, &mut CloudCluster, &mut Mesh3d, &mut Visibility), Without<Camera3d>>,
+) {
+    let eye = camera.translation.xz();
+    for (tf, mut cluster, mut mesh, mut visibility) in clouds {
+        let lod = CloudLod::from_distance(tf.translation.xz().distance(eye));
+        if lod == cluster.lod {
+            continue;
+        }
+
+        let shape = &assets.shapes[cluster.shape];
+        match lod {
+            CloudLod::Near => mesh.0 = shape.near.clone(),
+            CloudLod::Far => mesh.0 = shape.far.clone(),
+            CloudLod::Culled => (),
+        }
+
+        *visibility = if lod == CloudLod::Culled {
+            Visibility::Hidden
+        } else {
+            Visibility::Visible
+        };
+
+        cluster.lod = lod;
+    }
+}
+
 fn sample_cloud_size(
+    mut diagnostics: Diagnostics,
+    mut tick: Local<u32>,
+    clouds: Query<(&mut Transform, &CloudCluster)>,
+    noise: Res<NoiseFunc>,
+    settings: Res<CloudNoiseSettings>,
+    offset: Res<NoiseOffset>,
+) {
+    let start = Instant::now();
+    *tick = tick.wrapping_add(1);
+
+    for (mut tf, cluster) in clouds {
+        let Some(interval) = cluster.lod.update_interval() else {
+            continue;
+        };
+
+        if tick.wrapping_add(cluster.index) % interval != 0 {
+            continue;
+        }
+
+        let radius = cloud_radius(&noise, &settings, offset.0, tf.translation.xz());
+        tf.scale = Vec3::new(radius, radius / 2.0, radius);
+    }
+
+    let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+    diagnostics.add_measurement(&CLOUD_UPDATE_TIME, || elapsed);
+}
+
+fn sample_legacy_cloud_size(
+    mut diagnostics: Diagnostics,
     clouds: Query<&mut Transform, With<Cloud>>,
     noise: Res<NoiseFunc>,
+    settings: Res<CloudNoiseSettings>,
     offset: Res<NoiseOffset>,
 ) {
+    let start = Instant::now();
+
     for mut tf in clouds {
-        let p = tf.translation.xz() / CLOUD_NOISE_SCALE + offset.0;
-        let t: f32 = noise.sample(p); // [-1, 1]
-        // let t = (t + 1.0) / 2.0; // [0, 1]
-        let radius = t.clamp(0.0, 1.0) * CLOUD_MAX_RADIUS;
+        let radius = cloud_radius(&noise, &settings, offset.0, tf.translation.xz());
         tf.scale = Vec3::new(radius, radius / 2.0, radius);
     }
+
+    let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+    diagnostics.add_measurement(&CLOUD_UPDATE_TIME, || elapsed);
+}
+
+fn sample_cloud_shadows(
+    shadows: Query<(&mut Transform, &CloudShadow)>,
+    sun: Single<&Transform, (With<DirectionalLight>, Without<CloudShadow>)>,
+    noise: Res<NoiseFunc>,
+    settings: Res<CloudNoiseSettings>,
+    offset: Res<NoiseOffset>,
+) {
+    // light travels along the sun's forward vector; walk back up towards it
+    // to find which part of the cloud deck is casting onto each patch.
+    let to_sun = sun.back().as_vec3();
+    if to_sun.y <= 0.0 {
+        return;
+    }
+
+    let cell = LAKE_RADIUS * 2.0 / CLOUD_SHADOW_CELLS as f32;
+    let shift = to_sun.xz() * (CLOUD_HEIGHT / to_sun.y);
+
+    for (mut tf, shadow) in shadows {
+        let radius = cloud_radius(&noise, &settings, offset.0, shadow.ground + shift);
+        let r = radius / CLOUD_MAX_RADIUS * cell;
+        tf.scale = Vec3::new(r, 1.0, r);
+    }
 }
 
 fn update_lpf_cloud_speed(mut speed: ResMut<CloudSpeed>) {
     speed.actual += (speed.target - speed.actual) * 0.03;
 }
+
+fn update_lpf_cloud_cover(mut settings: ResMut<CloudNoiseSettings>) {
+    settings.coverage += (settings.target_coverage - settings.coverage) * 0.01;
+    settings.density += (settings.target_density - settings.density) * 0.01;
+}
+
+#[derive(Debug, Default)]
+struct FrameTimeStats {
+    total_ms: f64,
+    frames: u32,
+}
+
+impl FrameTimeStats {
+    fn mean(&self) -> Option<f64> {
+        (self.frames > 0).then(|| self.total_ms / self.frames as f64)
+    }
+}
+
+/// Running frame-time and cloud update averages for each renderer, shown in
+/// the debug panel and logged whenever the renderer is switched.
+#[derive(Resource, Debug, Default)]
+pub struct CloudFrameTimes {
+    legacy_frame: FrameTimeStats,
+    legacy_update: FrameTimeStats,
+    clustered_frame: FrameTimeStats,
+    clustered_update: FrameTimeStats,
+    warmup: u32,
+}
+
+impl CloudFrameTimes {
+    /// Mean milliseconds per frame, and per cloud update, with `renderer`.
+    pub fn mean_ms(&self, renderer: CloudRenderer) -> (Option<f64>, Option<f64>) {
+        match renderer {
+            CloudRenderer::Legacy => (self.legacy_frame.mean(), self.legacy_update.mean()),
+            CloudRenderer::Clustered => (self.clustered_frame.mean(), self.clustered_update.mean()),
+        }
+    }
+}
+
+/// Frames to skip after switching renderers, so spawning doesn't skew the average.
+const CLOUD_BENCH_WARMUP_FRAMES: u32 = 60;
+
+fn record_cloud_frame_times(
+    store: Res<DiagnosticsStore>,
+    renderer: Res<CloudRenderer>,
+    mut times: ResMut<CloudFrameTimes>,
+) {
+    if times.warmup < CLOUD_BENCH_WARMUP_FRAMES {
+        times.warmup += 1;
+        return;
+    }
+
+    let frame = store
+        .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|d| d.value());
+    let update = store.get(&CLOUD_UPDATE_TIME).and_then(|d| d.value());
+
+    let times = &mut *times;
+    let (frame_stats, update_stats) = match *renderer {
+        CloudRenderer::Legacy => (&mut times.legacy_frame, &mut times.legacy_update),
+        CloudRenderer::Clustered => (&mut times.clustered_frame, &mut times.clustered_update),
+    };
+
+    if let Some(ms) = frame {
+        frame_stats.total_ms += ms;
+        frame_stats.frames += 1;
+    }
+
+    if let Some(ms) = update {
+        update_stats.total_ms += ms;
+        update_stats.frames += 1;
+    }
+}
+
+fn toggle_cloud_renderer_on_f3(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    clouds: Query<Entity, With<Cloud>>,
+    assets: Res<CloudAssets>,
+    material: Res<CloudMaterial>,
+    mut renderer: ResMut<CloudRenderer>,
+    mut times: ResMut<CloudFrameTimes>,
+) {
+    if !keys.just_pressed(KeyCode::F3) {
+        return;
+    }
+
+    info!(
+        "Cloud frame time: legacy {:?} ms (update {:?} ms), clustered {:?} ms (update {:?} ms)",
+        times.legacy_frame.mean(),
+        times.legacy_update.mean(),
+        times.clustered_frame.mean(),
+        times.clustered_update.mean(),
+    );
+
+    times.warmup = 0;
+
+    for e in clouds {
+        commands.entity(e).despawn();
+    }
+
+    *renderer = match *renderer {
+        CloudRenderer::Legacy => CloudRenderer::Clustered,
+        CloudRenderer::Clustered => CloudRenderer::Legacy,
+    };
+
+    spawn_clouds(&mut commands, &assets, &material, *renderer);
+}
@@ -3,8 +3,12 @@
 use bevy::color::palettes::css::*;
 use bevy::prelude::*;
 
+use crate::audio::AudioSettings;
+use crate::behaviour::{Activity, Mind};
 use crate::child_rel::{DuckParent, Following};
+use crate::clouds::{CloudFrameTimes, CloudNoiseSettings, CloudRenderer};
 use crate::ducks::*;
+use crate::navigation::{NavGrid, NavPath, Obstacle};
 use crate::player::PlayerDuck;
 
 pub fn debug_plugin(app: &mut App) {
@@ -20,20 +24,33 @@ pub fn debug_plugin(app: &mut App) {
             draw_all_ducks_with_parent,
             draw_all_spatial_audio,
             draw_true_parents,
+            draw_activities,
+            draw_nav_paths,
+            draw_obstacles,
         )
             .run_if(is_debug_enabled),
     );
 
+    app.add_systems(OnEnter(DebugState::Enabled), spawn_tunables_panel);
+    app.add_systems(OnExit(DebugState::Enabled), despawn_tunables_panel);
+    app.add_systems(
+        Update,
+        (edit_tunables, update_tunables_panel)
+            .chain()
+            .run_if(is_debug_enabled),
+    );
+
+    app.insert_resource(SelectedTunable(0));
     app.insert_state(DebugState::Disabled);
     // app.add_systems(Update, draw_all_transforms);
 }
 
-fn is_debug_enabled(state: Res<State<DebugState>>) -> bool {
+pub fn is_debug_enabled(state: Res<State<DebugState>>) -> bool {
     *state == DebugState::Enabled
 }
 
 #[derive(States, Debug, Clone, Copy, Hash, PartialEq, Eq)]
-enum DebugState {
+pub enum DebugState {
     Enabled,
     Disabled,
 }
@@ -162,3 +179,160 @@ fn draw_true_parents(
     }
     Ok(())
 }
+
+/// A ring over each duck, coloured by what it's doing.
+fn draw_activities(mut gizmos: Gizmos, minds: Query<(&Transform, &Mind)>) {
+    for (tf, mind) in minds {
+        let p = tf.translation.with_y(DUCK_DEBUG_MARKERS_Y + 1.0);
+        let iso = Isometry3d::new(p, Quat::from_rotation_x(std::f32::consts::FRAC_PI_2));
+        gizmos.circle(iso, 0.6, mind.activity.color());
+    }
+}
+
+/// The route each duck is taking, corner by corner.
+fn draw_nav_paths(mut gizmos: Gizmos, ducks: Query<(&Transform, &TargetPosition, &NavPath)>) {
+    for (tf, tp, path) in ducks {
+        if path.remaining().is_empty() {
+            continue;
+        }
+        let points = std::iter::once(tf.translation)
+            .chain(path.remaining().iter().copied())
+            .chain(std::iter::once(tp.pos))
+            .map(|p| p.with_y(DUCK_DEBUG_MARKERS_Y - 1.0));
+        gizmos.linestrip(points, YELLOW);
+    }
+}
+
+/// Obstacles, and the grid cells they block.
+fn draw_obstacles(
+    mut gizmos: Gizmos,
+    grid: Res<NavGrid>,
+    obstacles: Query<(&Transform, &Obstacle)>,
+) {
+    let rot = Quat::from_rotation_x(std::f32::consts::FRAC_PI_2);
+    for (tf, obstacle) in obstacles {
+        let iso = Isometry3d::new(tf.translation.with_y(0.2), rot);
+        gizmos.circle(iso, obstacle.radius, ORANGE_RED);
+    }
+    for c in grid.obstacle_cells() {
+        let iso = Isometry3d::new(grid.centre_of(c).with_y(0.1), rot);
+        gizmos.rect(iso, Vec2::splat(grid.cell_size()), ORANGE.with_alpha(0.4));
+    }
+}
+
+#[derive(Resource)]
+struct SelectedTunable(usize);
+
+#[derive(Component)]
+struct TunablesPanel;
+
+fn spawn_tunables_panel(mut commands: Commands) {
+    commands.spawn((
+        TunablesPanel,
+        Node {
+            position_type: PositionType::Absolute,
+            right: px(8.0),
+            top: px(8.0),
+            padding: UiRect::all(px(8.0)),
+            ..default()
+        },
+        BackgroundColor(BLACK.with_alpha(0.5).into()),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        Text::default(),
+    ));
+}
+
+fn despawn_tunables_panel(mut commands: Commands, panels: Query<Entity, With<TunablesPanel>>) {
+    for e in panels {
+        commands.entity(e).despawn();
+    }
+}
+
+/// Up/down picks a tunable, left/right adjusts it. Cloud tunables come
+/// first, followed by the mixer.
+fn edit_tunables(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut selected: ResMut<SelectedTunable>,
+    mut clouds: ResMut<CloudNoiseSettings>,
+    mut mixer: ResMut<AudioSettings>,
+) {
+    let n_clouds = CloudNoiseSettings::TUNABLES.len();
+    let n = n_clouds + AudioSettings::TUNABLES.len();
+
+    if keys.just_pressed(KeyCode::ArrowUp) {
+        selected.0 = (selected.0 + n - 1) % n;
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        selected.0 = (selected.0 + 1) % n;
+    }
+
+    let mut steps = 0;
+    if keys.just_pressed(KeyCode::ArrowLeft) {
+        steps -= 1;
+    }
+    if keys.just_pressed(KeyCode::ArrowRight) {
+        steps += 1;
+    }
+
+    if steps == 0 {
+        return;
+    }
+
+    if selected.0 < n_clouds {
+        clouds.nudge_tunable(selected.0, steps);
+    } else {
+        mixer.nudge_tunable(selected.0 - n_clouds, steps);
+    }
+}
+
+fn update_tunables_panel(
+    selected: Res<SelectedTunable>,
+    clouds: Res<CloudNoiseSettings>,
+    mixer: Res<AudioSettings>,
+    renderer: Res<CloudRenderer>,
+    frame_times: Res<CloudFrameTimes>,
+    minds: Query<&Mind>,
+    text: Query<&mut Text, With<TunablesPanel>>,
+) {
+    let n_clouds = CloudNoiseSettings::TUNABLES.len();
+
+    let mut s = String::from("Clouds\n");
+    for (i, name) in CloudNoiseSettings::TUNABLES.iter().enumerate() {
+        let cursor = if i == selected.0 { ">" } else { " " };
+        s += &format!("{} {}: {:.2}\n", cursor, name, clouds.tunable(i));
+    }
+
+    s += "Mixer\n";
+    for (i, name) in AudioSettings::TUNABLES.iter().enumerate() {
+        let cursor = if i + n_clouds == selected.0 { ">" } else { " " };
+        s += &format!("{} {}: {:.2}\n", cursor, name, mixer.tunable(i));
+    }
+
+    // F3 switches renderer, so the two can be compared side by side
+    s += "Cloud frame time\n";
+    for r in [CloudRenderer::Legacy, CloudRenderer::Clustered] {
+        let cursor = if r == *renderer { "*" } else { " " };
+        let ms = |t: Option<f64>| t.map_or("-".to_owned(), |t| format!("{:.2} ms", t));
+        let (frame, update) = frame_times.mean_ms(r);
+        s += &format!(
+            "{} {:?}: {} (update {})\n",
+            cursor,
+            r,
+            ms(frame),
+            ms(update)
+        );
+    }
+
+    s += "Activities\n";
+    for a in Activity::ADULT.iter().chain(&Activity::DUCKLING) {
+        let n = minds.iter().filter(|m| m.activity == *a).count();
+        s += &format!("  {:?}: {}\n", a, n);
+    }
+
+    for mut t in text {
+        t.0 = s.clone();
+    }
+}
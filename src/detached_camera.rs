@@ -1,15 +1,27 @@
-#![allow(unused)]
-
+use bevy::input::mouse::AccumulatedMouseMotion;
 use bevy::prelude::*;
 
-fn camera_plugin(app: &mut App) {
-    app.add_systems(Update, move_camera);
-}
+/// Where the free-flying debug camera is. It's left behind when switching to
+/// another mode and picked up from the current view when switching back.
+#[derive(Resource, Debug, Default)]
+pub struct FreeFlyCamera(pub Transform);
+
+/// Metres per second; shift flies faster.
+const FLY_SPEED: f32 = 8.0;
+const FLY_BOOST: f32 = 4.0;
 
-fn move_camera(
+/// Radians per pixel of mouse drag.
+const LOOK_SENSITIVITY: f32 = 0.004;
+
+pub fn move_camera(
     keys: Res<ButtonInput<KeyCode>>,
-    mut camera: Single<&mut Transform, With<Camera3d>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
+    // real time, so it can fly while the game is paused
+    time: Res<Time<Real>>,
+    mut free: ResMut<FreeFlyCamera>,
 ) {
+    let camera = &mut free.0;
     let mut direction = Vec3::ZERO;
 
     if keys.pressed(KeyCode::KeyW) {
@@ -31,9 +43,19 @@ fn move_camera(
         direction += camera.up().as_vec3()
     }
 
-    let speed = 0.1;
+    let speed = if keys.pressed(KeyCode::ShiftLeft) {
+        FLY_SPEED * FLY_BOOST
+    } else {
+        FLY_SPEED
+    };
 
-    camera.translation += direction.normalize_or_zero() * speed;
+    camera.translation += direction.normalize_or_zero() * speed * time.delta_secs();
 
-    camera.look_at(Vec3::ZERO, Vec3::Y);
+    // look around by dragging
+    if mouse.pressed(MouseButton::Left) {
+        let (yaw, pitch, _) = camera.rotation.to_euler(EulerRot::YXZ);
+        let yaw = yaw - motion.delta.x * LOOK_SENSITIVITY;
+        let pitch = (pitch - motion.delta.y * LOOK_SENSITIVITY).clamp(-1.5, 1.5);
+        camera.rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0);
+    }
 }
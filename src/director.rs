@@ -0,0 +1,446 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::camera::{CameraMode, CinematicShot};
+use crate::weather::Weather;
+
+pub fn director_plugin(app: &mut App) {
+    app.add_systems(Update, (toggle_director_on_f8, direct_shot).chain());
+
+    app.add_observer(on_director_cue);
+
+    app.insert_resource(Director {
+        enabled: true,
+        rules: load_rules(),
+        shot: None,
+        since_last_shot: f32::INFINITY,
+    });
+}
+
+/// Compiled in, like the locales, so it works everywhere.
+const RULES: &str = include_str!("../assets/director.ron");
+
+/// Something worth cutting away to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub enum CueKind {
+    /// A duckling found its parent.
+    Reunion,
+    /// A predator caught a duckling, which got away somewhere else.
+    Caught,
+}
+
+/// Asks the director for a shot framing `subjects`. It may decline.
+#[derive(Event, Debug)]
+pub struct DirectorCue {
+    pub kind: CueKind,
+    pub subjects: Vec<Entity>,
+}
+
+/// How to shoot one kind of cue. See `assets/director.ron`.
+#[derive(Debug, Clone, Deserialize)]
+struct ShotRule {
+    enabled: bool,
+    cut: bool,
+    duration: f32,
+    time_scale: f32,
+    distance: f32,
+    height: f32,
+    skip_in_storm: bool,
+    priority: u8,
+    cooldown: f32,
+}
+
+fn load_rules() -> BTreeMap<CueKind, ShotRule> {
+    ron::from_str(RULES).unwrap_or_else(|e| {
+        error!("Failed to parse director rules: {}", e);
+        BTreeMap::new()
+    })
+}
+
+#[derive(Debug)]
+struct Shot {
+    rule: ShotRule,
+    subjects: Vec<Entity>,
+    elapsed: f32,
+    /// Which way the camera looks across the subjects, fixed once framed.
+    side: Option<Vec3>,
+    /// Handed back when the shot ends.
+    previous_mode: CameraMode,
+}
+
+/// Takes the camera for short, slowed-down shots of big moments, then
+/// gives it back.
+#[derive(Resource, Debug)]
+pub struct Director {
+    enabled: bool,
+    rules: BTreeMap<CueKind, ShotRule>,
+    shot: Option<Shot>,
+    since_last_shot: f32,
+}
+
+impl Director {
+    /// The camera mode the shot in progress will hand back, if there is one.
+    pub fn handback_mode(&self) -> Option<CameraMode> {
+        self.shot.as_ref().map(|shot| shot.previous_mode)
+    }
+}
+
+fn toggle_director_on_f8(keys: Res<ButtonInput<KeyCode>>, mut director: ResMut<Director>) {
+    if keys.just_pressed(KeyCode::F8) {
+        director.enabled = !director.enabled;
+        info!("Camera director: {}", director.enabled);
+    }
+}
+
+/// What's going on when a cue comes in.
+#[derive(Debug, Clone, Copy)]
+struct Situation {
+    enabled: bool,
+    storm: bool,
+    free_fly: bool,
+    since_last_shot: f32,
+    /// Of the shot in progress, if any.
+    shot_priority: Option<u8>,
+}
+
+/// Whether a cue shot by `rule` gets the camera.
+fn takes_cue(rule: &ShotRule, now: Situation) -> bool {
+    now.enabled
+        && rule.enabled
+        && !(rule.skip_in_storm && now.storm)
+        && now.since_last_shot >= rule.cooldown
+        // don't take the camera off someone flying around debugging
+        && !now.free_fly
+        // only a more important cue may interrupt a shot in progress
+        && now.shot_priority.is_none_or(|p| rule.priority > p)
+}
+
+fn on_director_cue(
+    event: On<DirectorCue>,
+    mut director: ResMut<Director>,
+    mode: Res<CameraMode>,
+    weather: Res<State<Weather>>,
+) {
+    let Some(rule) = director.rules.get(&event.kind).cloned() else {
+        return;
+    };
+
+    let now = Situation {
+        enabled: director.enabled,
+        storm: **weather == Weather::Thunderstorm,
+        free_fly: *mode == CameraMode::FreeFly,
+        since_last_shot: director.since_last_shot,
+        shot_priority: director.shot.as_ref().map(|s| s.rule.priority),
+    };
+    if !takes_cue(&rule, now) {
+        return;
+    }
+
+    let previous_mode = match &director.shot {
+        Some(shot) => shot.previous_mode,
+        None => *mode,
+    };
+
+    debug!("Director cutting to {:?}", event.kind);
+    director.shot = Some(Shot {
+        rule,
+        subjects: event.subjects.clone(),
+        elapsed: 0.0,
+        side: None,
+        previous_mode,
+    });
+}
+
+fn direct_shot(
+    mut director: ResMut<Director>,
+    mut mode: ResMut<CameraMode>,
+    mut shot: ResMut<CinematicShot>,
+    mut time: ResMut<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
+    transforms: Query<&Transform>,
+) {
+    let dt = real_time.delta_secs();
+    let director = &mut *director;
+    let Some(current) = &mut director.shot else {
+        director.since_last_shot += dt;
+        return;
+    };
+
+    // the player switched the camera themselves
+    if mode.is_changed() && *mode != CameraMode::Cinematic {
+        director.shot = None;
+        director.since_last_shot = 0.0;
+        time.set_relative_speed(1.0);
+        return;
+    }
+
+    current.elapsed += dt;
+    let positions: Vec<Vec3> = transforms
+        .iter_many(&current.subjects)
+        .map(|tf| tf.translation)
+        .collect();
+
+    if current.elapsed >= current.rule.duration || positions.is_empty() {
+        *mode = current.previous_mode;
+        director.shot = None;
+        director.since_last_shot = 0.0;
+        time.set_relative_speed(1.0);
+        return;
+    }
+
+    // side on to the subjects, far enough back to fit them all in
+    let focus = positions.iter().sum::<Vec3>() / positions.len() as f32;
+    let spread = positions
+        .iter()
+        .map(|p| p.distance(focus))
+        .fold(0.0, f32::max);
+    let side = *current.side.get_or_insert_with(|| {
+        let across = positions
+            .iter()
+            .map(|p| (*p - focus).with_y(0.0))
+            .find(|d| d.length_squared() > 0.01)
+            .unwrap_or(Vec3::X)
+            .normalize();
+        Vec3::Y.cross(across)
+    });
+    let distance = current.rule.distance + spread * 2.0;
+
+    shot.position = focus + side * distance + Vec3::Y * current.rule.height;
+    shot.focus = focus;
+    shot.cut = current.rule.cut;
+
+    if *mode != CameraMode::Cinematic {
+        *mode = CameraMode::Cinematic;
+    }
+    time.set_relative_speed(current.rule.time_scale);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::state::app::StatesPlugin;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    /// Real seconds per update.
+    const STEP: f32 = 0.1;
+
+    fn rule() -> ShotRule {
+        ShotRule {
+            enabled: true,
+            cut: false,
+            duration: 2.0,
+            time_scale: 0.5,
+            distance: 5.0,
+            height: 2.0,
+            skip_in_storm: true,
+            priority: 1,
+            cooldown: 6.0,
+        }
+    }
+
+    fn quiet() -> Situation {
+        Situation {
+            enabled: true,
+            storm: false,
+            free_fly: false,
+            since_last_shot: f32::INFINITY,
+            shot_priority: None,
+        }
+    }
+
+    #[test]
+    fn takes_cues_when_nothing_is_going_on() {
+        assert!(takes_cue(&rule(), quiet()));
+    }
+
+    #[test]
+    fn declines_when_switched_off() {
+        let disabled = ShotRule {
+            enabled: false,
+            ..rule()
+        };
+        assert!(!takes_cue(&disabled, quiet()));
+        let off = Situation {
+            enabled: false,
+            ..quiet()
+        };
+        assert!(!takes_cue(&rule(), off));
+    }
+
+    #[test]
+    fn storms_only_stop_rules_that_skip_them() {
+        let storm = Situation {
+            storm: true,
+            ..quiet()
+        };
+        assert!(!takes_cue(&rule(), storm));
+        let any_weather = ShotRule {
+            skip_in_storm: false,
+            ..rule()
+        };
+        assert!(takes_cue(&any_weather, storm));
+    }
+
+    #[test]
+    fn waits_out_the_cooldown() {
+        let just_cut = Situation {
+            since_last_shot: 5.9,
+            ..quiet()
+        };
+        assert!(!takes_cue(&rule(), just_cut));
+        let later = Situation {
+            since_last_shot: 6.0,
+            ..quiet()
+        };
+        assert!(takes_cue(&rule(), later));
+    }
+
+    #[test]
+    fn leaves_the_free_fly_camera_alone() {
+        let flying = Situation {
+            free_fly: true,
+            ..quiet()
+        };
+        assert!(!takes_cue(&rule(), flying));
+    }
+
+    #[test]
+    fn only_more_important_cues_interrupt() {
+        let shooting = |priority| Situation {
+            shot_priority: Some(priority),
+            ..quiet()
+        };
+        assert!(takes_cue(&rule(), shooting(0)));
+        assert!(!takes_cue(&rule(), shooting(1)));
+        assert!(!takes_cue(&rule(), shooting(2)));
+    }
+
+    #[test]
+    fn every_cue_has_a_rule() {
+        let rules = load_rules();
+        for kind in [CueKind::Reunion, CueKind::Caught] {
+            assert!(rules.contains_key(&kind), "no rule for {kind:?}");
+        }
+        assert!(rules[&CueKind::Caught].priority > rules[&CueKind::Reunion].priority);
+    }
+
+    fn shooting_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin));
+        app.add_plugins(director_plugin);
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.insert_resource(CameraMode::Orbit);
+        app.insert_resource(CinematicShot {
+            position: Vec3::ZERO,
+            focus: Vec3::ZERO,
+            cut: false,
+        });
+        app.insert_state(Weather::Clear);
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            STEP,
+        )));
+        app.update();
+        app
+    }
+
+    fn cue(app: &mut App, kind: CueKind) {
+        let world = app.world_mut();
+        let duck = world.spawn(Transform::from_xyz(3.0, 0.0, 4.0)).id();
+        world.trigger(DirectorCue {
+            kind,
+            subjects: vec![duck],
+        });
+        app.update();
+    }
+
+    fn rule_for(app: &App, kind: CueKind) -> ShotRule {
+        app.world().resource::<Director>().rules[&kind].clone()
+    }
+
+    fn shooting(app: &App) -> Option<CueKind> {
+        let director = app.world().resource::<Director>();
+        let shot = director.shot.as_ref()?;
+        director
+            .rules
+            .iter()
+            .find(|(_, rule)| rule.priority == shot.rule.priority)
+            .map(|(kind, _)| *kind)
+    }
+
+    fn mode(app: &App) -> CameraMode {
+        *app.world().resource::<CameraMode>()
+    }
+
+    fn speed(app: &App) -> f32 {
+        app.world().resource::<Time<Virtual>>().relative_speed()
+    }
+
+    /// Updates until the shot is over, or panics if it takes far too long.
+    fn finish_shot(app: &mut App) {
+        for _ in 0..1000 {
+            if app.world().resource::<Director>().shot.is_none() {
+                return;
+            }
+            app.update();
+        }
+        panic!("the shot never ended");
+    }
+
+    #[test]
+    fn shots_hand_back_the_camera_and_the_speed() {
+        let mut app = shooting_app();
+        let rule = rule_for(&app, CueKind::Reunion);
+
+        cue(&mut app, CueKind::Reunion);
+        assert_eq!(mode(&app), CameraMode::Cinematic);
+        assert_eq!(speed(&app), rule.time_scale);
+        let focus = app.world().resource::<CinematicShot>().focus;
+        assert_eq!(focus, Vec3::new(3.0, 0.0, 4.0));
+
+        let start = app.world().resource::<Time<Real>>().elapsed_secs();
+        finish_shot(&mut app);
+        let took = app.world().resource::<Time<Real>>().elapsed_secs() - start;
+        assert!(took >= rule.duration - 2.0 * STEP, "ended after {took}s");
+        assert!(took <= rule.duration + 2.0 * STEP, "ended after {took}s");
+
+        assert_eq!(mode(&app), CameraMode::Orbit);
+        assert_eq!(speed(&app), 1.0);
+        // which counts for the cooldown
+        cue(&mut app, CueKind::Reunion);
+        assert_eq!(shooting(&app), None);
+    }
+
+    #[test]
+    fn switching_the_camera_cancels_the_shot() {
+        let mut app = shooting_app();
+        cue(&mut app, CueKind::Reunion);
+        assert_eq!(mode(&app), CameraMode::Cinematic);
+
+        *app.world_mut().resource_mut::<CameraMode>() = CameraMode::TopDown;
+        app.update();
+        assert_eq!(shooting(&app), None);
+        assert_eq!(mode(&app), CameraMode::TopDown);
+        assert_eq!(speed(&app), 1.0);
+    }
+
+    #[test]
+    fn a_catch_interrupts_a_reunion_and_still_hands_back() {
+        let mut app = shooting_app();
+        cue(&mut app, CueKind::Reunion);
+        assert_eq!(shooting(&app), Some(CueKind::Reunion));
+
+        cue(&mut app, CueKind::Caught);
+        assert_eq!(shooting(&app), Some(CueKind::Caught));
+        assert_eq!(speed(&app), rule_for(&app, CueKind::Caught).time_scale);
+
+        // but not the other way round
+        cue(&mut app, CueKind::Reunion);
+        assert_eq!(shooting(&app), Some(CueKind::Caught));
+
+        finish_shot(&mut app);
+        assert_eq!(mode(&app), CameraMode::Orbit);
+        assert_eq!(speed(&app), 1.0);
+    }
+}
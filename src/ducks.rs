@@ -1,42 +1,51 @@
-use bevy::audio::Volume;
 use bevy::color::palettes::tailwind::*;
 use bevy::prelude::*;
 use bevy_rich_text3d::Text3d;
-use rand::*;
 
+use crate::atmosphere::SightRange;
+use crate::behaviour::{Activity, BehaviourSystems, CurrentBehaviour, Mind};
 use crate::child_rel::*;
 use crate::despawn_after::DespawnAfter;
-use crate::math::{random_chance, random_vec};
+use crate::director::{CueKind, DirectorCue};
+use crate::growth::Growth;
+use crate::hud::Notification;
+use crate::level::PopulateLevel;
+use crate::locale::Localization;
+use crate::math::{GameRng, cosmetic_random_chance, cosmetic_random_range};
+use crate::navigation::{NavPath, NavigationSystems};
 use crate::particles::{RippleEmitter, Splash};
+use crate::perception::{Perception, PerceptionSystems};
 use crate::player::PlayerDuck;
+use crate::quacks::QuackKind;
 use crate::text_bubble::SpawnScoreMarker;
 use crate::text_bubble::{Quack, ScoreLabel};
-use crate::weather::{LightningEvent, Weather};
+use crate::voice::Voice;
+use crate::weather::LightningEvent;
 
 pub fn player_plugin(app: &mut App) {
-    app.add_systems(Startup, add_ducks);
-
     app.add_systems(
         FixedUpdate,
         (
             handle_duck_jump_messages,
             assign_true_parents,
-            assign_parent_to_parentless_ducks,
+            assign_parent_to_parentless_ducks.after(PerceptionSystems),
+            ducklings_lose_sight_of_leaders.after(PerceptionSystems),
             damp_velocity,
             apply_gravity_to_ducks,
             update_ducks_above_sea_level,
             accelerate_ducks,
-            update_tracking_force_for_target_seekers,
+            update_tracking_force_for_target_seekers
+                .after(BehaviourSystems)
+                .after(NavigationSystems),
             update_separation_force,
-            randomly_wander,
             update_target_pos_for_ducks_with_parents,
             control_boids,
             propagate_duck_physics,
             move_duck_heads,
             update_head_turning_transform,
-            spawn_sounds_on_quack,
             update_score_labels,
-        ),
+        )
+            .chain(),
     );
 
     // quacking behaviors
@@ -47,20 +56,25 @@ pub fn player_plugin(app: &mut App) {
             ducks_quack_based_on_current_parents,
             celebrating_ducks_quack_excitedly,
             adult_ducks_occasionally_pontificate,
-        ),
+        )
+            .chain(),
     );
 
     app.add_systems(
         FixedUpdate,
-        (spawn_particles_if_kicking, spawn_ripples_if_kicking),
+        (spawn_particles_if_kicking, spawn_ripples_if_kicking).chain(),
     );
 
     app.add_message::<DuckJump>();
 
+    app.add_observer(add_ducks);
     app.add_observer(on_add_duck);
     app.add_observer(ducklings_freak_out_on_lightning);
 }
 
+/// Localization keys used in this module.
+pub const LOCALE_KEYS: &[&str] = &["notify.reunited", "score.label"];
+
 #[derive(Component, Default, Debug)]
 pub struct Duck {
     pub is_kicking: bool,
@@ -121,10 +135,12 @@ impl Duck {
 }
 
 #[derive(Component, Event, Debug)]
-struct AddDuck {
-    transform: Transform,
-    is_player: bool,
-    is_child: bool,
+pub struct AddDuck {
+    pub transform: Transform,
+    pub is_player: bool,
+    pub is_child: bool,
+    /// Build the duck on this entity rather than a new one.
+    pub entity: Option<Entity>,
 }
 
 #[derive(Component, Debug, Default)]
@@ -148,37 +164,61 @@ struct Celebrating {
     duck: Entity,
 }
 
-fn celebrating_ducks_quack_excitedly(mut commands: Commands, cel: Query<&Celebrating>) {
+fn celebrating_ducks_quack_excitedly(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    cel: Query<&Celebrating>,
+) {
     for cel in cel {
-        if random_chance(0.03) {
-            commands.write_message(Quack::info(cel.duck, "Happy quack!"));
+        if rng.chance(0.03) {
+            commands.write_message(Quack::new(cel.duck, QuackKind::Celebrating));
         }
     }
 }
 
+/// A duckling that has found its true parent.
 #[derive(Component)]
-struct Done;
+pub struct Done;
+
+/// A duckling lost track of the duck it was following and gave up on it.
+#[derive(Event, Debug)]
+pub struct LostLeader {
+    pub duckling: Entity,
+    pub leader: Entity,
+}
 
 fn assign_parent_to_parentless_ducks(
     mut commands: Commands,
-    adults: Query<(Entity, &Transform, Option<&PlayerDuck>), (With<Duck>, Without<Duckling>)>,
+    behaviour: CurrentBehaviour,
+    sight: Res<SightRange>,
+    adults: Query<(Entity, Option<&PlayerDuck>), (With<Duck>, Without<Duckling>)>,
     ducklings: Query<
-        (Entity, &Transform, Option<&Following>, &DuckParent),
+        (
+            Entity,
+            &Transform,
+            Option<&Following>,
+            &DuckParent,
+            &Perception,
+        ),
         (With<Duckling>, Without<Done>),
     >,
 ) -> Result {
-    for (duckling_id, p, following, true_parent) in ducklings {
+    let perception_radius = behaviour.get().perception_radius.min(**sight);
+
+    for (duckling_id, p, following, true_parent, perception) in ducklings {
         // if a duckling is already following its parent, we're done here.
-        if let Some(follow) = following {
-            if true_parent.0 == follow.0 {
-                continue;
-            }
+        if let Some(follow) = following
+            && true_parent.0 == follow.0
+        {
+            continue;
         }
 
-        for (adult_id, q, is_player) in adults {
-            // don't even consider following an adult if it's not close enough.
-            let dist = p.translation.distance(q.translation);
-            if dist > 15.0 {
+        for (adult_id, is_player) in adults {
+            // don't even consider following an adult it hasn't noticed close by.
+            let Some(seen) = perception.recall(adult_id) else {
+                continue;
+            };
+            if seen.position.distance(p.translation) > perception_radius {
                 continue;
             }
 
@@ -192,13 +232,18 @@ fn assign_parent_to_parentless_ducks(
                 commands
                     .entity(duckling_id)
                     .insert((Following(adult_id), Done));
+                commands.trigger(Notification::new("notify.reunited"));
+                commands.trigger(DirectorCue {
+                    kind: CueKind::Reunion,
+                    subjects: vec![duckling_id, adult_id],
+                });
                 commands.spawn((
                     Celebrating { duck: duckling_id },
                     DespawnAfter::new(std::time::Duration::from_secs(3)),
                 ));
 
                 if let Some(old) = old_parent {
-                    let quack = Quack::important(old, "Here, take your kid please.");
+                    let quack = Quack::new(old, QuackKind::Scolding);
                     commands.write_message(quack);
                 }
 
@@ -214,29 +259,57 @@ fn assign_parent_to_parentless_ducks(
     Ok(())
 }
 
+/// Ducklings that haven't found their true parent yet stop following
+/// whoever they were tagging along with once they've lost track of that duck.
+fn ducklings_lose_sight_of_leaders(
+    mut commands: Commands,
+    ducklings: Query<(Entity, &Following, &Perception), (With<Duckling>, Without<Done>)>,
+) {
+    for (duckling, following, perception) in ducklings {
+        if !perception.remembers(following.0) {
+            commands.entity(duckling).remove::<Following>();
+            commands.write_message(Quack::new(duckling, QuackKind::Lost));
+            commands.trigger(LostLeader {
+                duckling,
+                leader: following.0,
+            });
+        }
+    }
+}
+
 const NUM_CHILDREN: usize = 20;
 const NUM_ADULTS: usize = 3;
 
-fn add_ducks(mut commands: Commands) {
+fn add_ducks(
+    _event: On<PopulateLevel>,
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    existing: Query<Entity, With<Duck>>,
+) {
+    for e in existing {
+        commands.entity(e).despawn();
+    }
+
     commands.trigger(AddDuck {
         transform: Transform::default(),
         is_player: true,
         is_child: false,
+        entity: None,
     });
 
     let mut spawn_duck = |is_child: bool| {
-        let r = rand::rng().random_range(2.0..100.0);
-        let a = rand::rng().random_range(0.0..std::f32::consts::PI * 2.0);
+        let r = rng.range(2.0..100.0);
+        let a = rng.range(0.0..std::f32::consts::PI * 2.0);
 
         let x = r * a.cos();
         let z = r * a.sin();
 
-        let angle = rand::rng().random_range(0.0..std::f32::consts::PI * 2.0);
+        let angle = rng.range(0.0..std::f32::consts::PI * 2.0);
 
         let scale = if is_child {
-            random_range(0.2..0.3)
+            rng.range(0.2..0.3)
         } else {
-            random_range(0.6..1.0)
+            rng.range(0.6..1.0)
         };
 
         let transform = Transform::from_xyz(x, 0.0, z)
@@ -247,6 +320,7 @@ fn add_ducks(mut commands: Commands) {
             transform,
             is_player: false,
             is_child,
+            entity: None,
         });
     };
 
@@ -259,6 +333,17 @@ fn add_ducks(mut commands: Commands) {
     }
 }
 
+/// Identifies an adult's family in indicators and on the map. Ducklings use
+/// their true parent's.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct FamilyColor(pub Color);
+
+impl FamilyColor {
+    pub fn random() -> Self {
+        Self(Color::hsl(cosmetic_random_range(0.0..360.0), 0.75, 0.6))
+    }
+}
+
 #[derive(Component)]
 pub struct HeadRoot(Entity);
 
@@ -273,9 +358,10 @@ fn update_head_turning_transform(
     Ok(())
 }
 
-fn on_add_duck(
+pub fn on_add_duck(
     event: On<AddDuck>,
     mut commands: Commands,
+    mut rng: ResMut<GameRng>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
@@ -283,7 +369,7 @@ fn on_add_duck(
     let head = meshes.add(Capsule3d::new(0.3, 1.0));
     let eye = meshes.add(Sphere::new(0.05));
 
-    let color = Srgba::gray(rand::rng().random_range(0.2..0.99));
+    let color = Srgba::gray(cosmetic_random_range(0.2..0.99));
 
     let material = materials.add(StandardMaterial::from_color(color));
     let bill_material = materials.add(StandardMaterial::from_color(YELLOW_400));
@@ -308,16 +394,20 @@ fn on_add_duck(
     let left_eye_transform = Transform::from_xyz(-eye_distance / 2.0, 0.6, 0.2);
 
     let speed_mod = if event.is_child {
-        random_range(1.3..=2.1)
+        rng.range(1.3..=2.1)
     } else {
-        random_range(0.9..=1.1)
+        rng.range(0.9..=1.1)
     };
 
-    let root = commands
-        .spawn((
+    let mut root = match event.entity {
+        Some(e) => commands.entity(e),
+        None => commands.spawn_empty(),
+    };
+    let root = root
+        .insert((
             Duck {
-                actual_head_angle: rand::rng().random_range(-0.3..=0.3),
-                is_kicking: random_chance(0.2),
+                actual_head_angle: rng.range(-0.3..=0.3),
+                is_kicking: rng.chance(0.2),
                 velocity: Vec3::Y * 3.0,
                 speed_mod,
                 ..default()
@@ -338,6 +428,7 @@ fn on_add_duck(
         .id();
 
     if !event.is_child && !event.is_player {
+        commands.entity(root).insert(FamilyColor::random());
         commands.trigger(SpawnScoreMarker { duck: root });
     }
 
@@ -363,20 +454,45 @@ fn on_add_duck(
 
     commands.entity(root).add_child(head);
 
+    // ducklings get a provisional voice until their true parent is known
+    let voice = if event.is_child {
+        Voice::child_of(&Voice::random())
+    } else {
+        Voice::random()
+    };
+    commands.entity(root).insert(voice);
+
     if event.is_child {
-        commands.entity(root).insert(Duckling);
+        commands.entity(root).insert((
+            Duckling,
+            Growth::hatchling(event.transform.scale.x, speed_mod),
+        ));
     }
 
     if !event.is_player {
-        commands.entity(root).insert(Boid::default());
+        commands.entity(root).insert((
+            Boid::default(),
+            NavPath::default(),
+            Mind::new(event.is_child),
+        ));
+        if event.is_child {
+            commands.entity(root).insert(Perception::duckling());
+        } else {
+            commands.entity(root).insert(Perception::adult());
+        }
     }
 }
 
+/// Followers make for their leader, unless they've gone off exploring, in
+/// which case they may well lose sight of it.
 fn update_target_pos_for_ducks_with_parents(
-    ducks: Query<(&Following, &mut TargetPosition)>,
+    ducks: Query<(&Following, &mut TargetPosition, Option<&Mind>)>,
     transforms: Query<&Transform>,
 ) -> Result {
-    for (parent, mut tp) in ducks {
+    for (parent, mut tp, mind) in ducks {
+        if mind.is_some_and(|m| m.activity == Activity::Exploring) {
+            continue;
+        }
         let tf = transforms.get(parent.0)?;
         tp.pos = tf.translation;
     }
@@ -423,11 +539,13 @@ fn accelerate_ducks(ducks: Query<(&mut Duck, &Transform)>) {
     }
 }
 
-fn move_duck_heads(ducks: Query<&mut Duck>) {
-    let max_rate = 0.06;
-    for mut duck in ducks {
-        if rand::rng().random_bool(0.01) {
-            duck.target_head_angle = rand::rng().random_range(-2.0..=2.0);
+/// Adults missing their children look about more, and quicker.
+fn move_duck_heads(mut rng: ResMut<GameRng>, ducks: Query<(&mut Duck, Option<&Mind>)>) {
+    for (mut duck, mind) in ducks {
+        let agitation = mind.map(|m| m.agitation()).unwrap_or(0.0);
+        let max_rate = 0.06 * (1.0 + 2.0 * agitation);
+        if rng.chance(0.01 + 0.08 * agitation) {
+            duck.target_head_angle = rng.range(-2.0..=2.0);
         }
 
         let delta = duck.target_head_angle - duck.actual_head_angle;
@@ -450,27 +568,34 @@ fn propagate_duck_physics(duck: Query<(&Duck, &mut Transform)>, time: Res<Time<F
 pub struct Boid {
     pub separation: Vec3,
     pub seek_target: Vec3,
+    /// Away from predators.
+    pub flee: Vec3,
+    /// Around obstacles.
+    pub avoid: Vec3,
 }
 
 impl Boid {
     pub fn total_force(&self) -> Vec3 {
-        self.seek_target + self.separation
+        self.seek_target + self.separation + self.flee + self.avoid
     }
 }
 
 fn update_tracking_force_for_target_seekers(
-    boids: Query<(&mut Boid, &Transform, &TargetPosition)>,
+    boids: Query<(&mut Boid, &Transform, &TargetPosition, Option<&NavPath>)>,
 ) {
-    for (mut boid, tf, tp) in boids {
-        let delta = tp.pos - tf.translation;
+    for (mut boid, tf, tp, path) in boids {
+        let goal = path.and_then(|p| p.waypoint()).unwrap_or(tp.pos);
+        let delta = goal - tf.translation;
         boid.seek_target = delta.normalize_or_zero() * delta.length().clamp(0.0, 10.0);
     }
 }
 
 fn update_separation_force(
+    behaviour: CurrentBehaviour,
     boids: Query<(Entity, &mut Boid, &Transform, Option<&Duckling>)>,
     ducks: Query<(Entity, &Transform, Option<&Duckling>), With<Duck>>,
 ) {
+    let duckling_weight = behaviour.get().duckling_separation;
     for (e1, mut boid, p, ego) in boids {
         boid.separation = Vec3::ZERO;
         for (e2, q, other) in ducks {
@@ -485,7 +610,7 @@ fn update_separation_force(
                 (true, true) => 200.0,
                 (true, false) => 0.0,
                 (false, true) => 10.0,
-                (false, false) => 1.0,
+                (false, false) => duckling_weight,
             };
 
             let delta = p.translation - q.translation;
@@ -497,20 +622,6 @@ fn update_separation_force(
     }
 }
 
-fn randomly_wander(targets: Query<&mut TargetPosition>) {
-    for mut target in targets {
-        if random_chance(0.001) {
-            let delta = random_vec(0.1, 4.0);
-            target.pos.x += delta.x;
-            target.pos.z += delta.y;
-        } else if random_chance(0.00003) {
-            let pos = random_vec(0.0, 200.0);
-            target.pos.x = pos.x;
-            target.pos.z = pos.y;
-        }
-    }
-}
-
 fn control_boids(ducks: Query<(&mut Duck, &Transform, &Boid)>) {
     for (mut duck, tf, boid) in ducks {
         let force = boid.total_force();
@@ -524,9 +635,9 @@ fn spawn_particles_if_kicking(
 ) {
     for (duck, transform) in ducks {
         if duck.is_boosting && duck.is_in_water() {
-            let vx = rand::rng().random_range(-5.0..=5.0);
-            let vy = rand::rng().random_range(2.0..=5.0);
-            let vz = rand::rng().random_range(-5.0..=5.0);
+            let vx = cosmetic_random_range(-5.0..=5.0);
+            let vy = cosmetic_random_range(2.0..=5.0);
+            let vz = cosmetic_random_range(-5.0..=5.0);
 
             let splash = Splash {
                 position: transform.translation,
@@ -543,36 +654,35 @@ fn spawn_ripples_if_kicking(ducks: Query<(&Duck, &mut RippleEmitter)>) {
         if !duck.is_in_water() {
             continue;
         }
-        emitter.is_on = duck.is_kicking || random_chance(0.001);
+        emitter.is_on = duck.is_kicking || cosmetic_random_chance(0.001);
     }
 }
 
 fn ducklings_randomly_quack(
     mut commands: Commands,
+    mut rng: ResMut<GameRng>,
     ducks: Query<Entity, With<Duckling>>,
-    weather: Res<State<Weather>>,
+    behaviour: CurrentBehaviour,
 ) {
-    let rate = match **weather {
-        Weather::Clear => 0.002,
-        Weather::Thunderstorm => 0.02,
-    };
+    let rate = behaviour.get().duckling_quack_chance;
 
     for duck in ducks {
-        if random_chance(rate) {
-            commands.write_message(Quack::noise(duck, "Quack."));
+        if rng.chance(rate) {
+            commands.write_message(Quack::new(duck, QuackKind::Contented));
         }
     }
 }
 
 fn ducks_quack_based_on_current_parents(
     mut commands: Commands,
+    mut rng: ResMut<GameRng>,
     ducks: Query<(Entity, &DuckParent, &Following), With<Duckling>>,
 ) {
     for (e, true_parent, actual_parent) in ducks {
-        if true_parent.0 != actual_parent.0 && random_chance(0.01) {
-            commands.write_message(Quack::noise(e, "Where is my parent?"));
-        } else if true_parent.0 == actual_parent.0 && random_chance(0.004) {
-            commands.write_message(Quack::info(e, "Contented quack."));
+        if true_parent.0 != actual_parent.0 && rng.chance(0.01) {
+            commands.write_message(Quack::new(e, QuackKind::Lost));
+        } else if true_parent.0 == actual_parent.0 && rng.chance(0.004) {
+            commands.write_message(Quack::new(e, QuackKind::Contented));
         }
     }
 }
@@ -580,59 +690,38 @@ fn ducks_quack_based_on_current_parents(
 fn ducklings_freak_out_on_lightning(
     _event: On<LightningEvent>,
     mut commands: Commands,
+    mut rng: ResMut<GameRng>,
     ducklings: Query<Entity, With<Duckling>>,
 ) {
     for duck in ducklings {
-        if random_chance(0.7) {
-            commands.write_message(Quack::info(duck, "AHHHH!!"));
+        if rng.chance(0.7) {
+            commands.write_message(Quack::new(duck, QuackKind::Panicked));
             commands.write_message(DuckJump { duck });
         }
     }
 }
 
-fn spawn_sounds_on_quack(
-    mut messages: MessageReader<Quack>,
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-) {
-    for quack in messages.read() {
-        let id = random_range(1..=4);
-        let name = format!("wek{id}.ogg");
-        let speed = random_range(0.95..=1.5);
-
-        let sound = commands
-            .spawn((
-                Transform::IDENTITY,
-                AudioPlayer::new(asset_server.load(name)),
-                PlaybackSettings::DESPAWN
-                    .with_spatial(true)
-                    .with_speed(speed)
-                    .with_volume(Volume::Linear(1.0)),
-            ))
-            .id();
-
-        commands.entity(quack.entity).add_child(sound);
-    }
-}
 fn adult_ducks_occasionally_pontificate(
     mut commands: Commands,
+    mut rng: ResMut<GameRng>,
     ducks: Query<Entity, (With<Duck>, Without<Duckling>)>,
 ) {
     for duck in ducks {
-        if random_chance(0.001) {
-            commands.write_message(Quack::noise(duck, "How come Aquaman can control whales?"));
+        if rng.chance(0.001) {
+            commands.write_message(Quack::new(duck, QuackKind::Musing));
         }
     }
 }
 
 pub fn assign_true_parents(
     mut commands: Commands,
+    mut rng: ResMut<GameRng>,
     ducklings: Query<Entity, (With<Duckling>, Without<DuckParent>)>,
     adults: Query<Entity, (With<Duck>, Without<Duckling>, Without<PlayerDuck>)>,
 ) {
     for duckling in ducklings {
         for adult in adults {
-            if random_chance(0.01) {
+            if rng.chance(0.01) {
                 let p = DuckParent(adult);
                 info!("Assigned duckling {} a true parent of {}", duckling, adult);
                 commands.entity(duckling).insert(p);
@@ -648,6 +737,7 @@ pub struct DuckJump {
 
 pub fn handle_duck_jump_messages(
     mut messages: MessageReader<DuckJump>,
+    mut rng: ResMut<GameRng>,
     mut ducks: Query<&mut Duck>,
 ) {
     for msg in messages.read() {
@@ -655,7 +745,7 @@ pub fn handle_duck_jump_messages(
             return;
         };
 
-        let vel = random_range(4.0..=11.0);
+        let vel = rng.range(4.0..=11.0);
 
         duck.velocity.y += vel;
     }
@@ -665,11 +755,15 @@ pub fn update_score_labels(
     text: Query<(&mut Text3d, &ScoreLabel)>,
     children: Query<&DuckChildren>,
     following: Query<&FollowedBy>,
+    loc: Res<Localization>,
 ) -> Result {
     for (mut t, label) in text {
         let n_children = children.get(label.duck).map(|c| c.len()).unwrap_or(0);
         let n_following = following.get(label.duck).map(|c| c.len()).unwrap_or(0);
-        let s = format!("{}/{}", n_following, n_children);
+        let s = loc.format(
+            "score.label",
+            &[("following", &n_following), ("children", &n_children)],
+        );
         *t = Text3d::new(s)
     }
     Ok(())
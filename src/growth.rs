@@ -0,0 +1,397 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::behaviour::Mind;
+use crate::child_rel::{DuckParent, Following};
+use crate::ducks::{AddDuck, Done, Duck, Duckling, FamilyColor};
+use crate::hud::Notification;
+use crate::math::GameRng;
+use crate::perception::Perception;
+use crate::text_bubble::SpawnScoreMarker;
+use crate::voice::Voice;
+
+pub fn growth_plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (grow_ducklings, mature_ducklings, hatch_broods).chain(),
+    );
+
+    app.insert_resource(load_curves());
+}
+
+/// Localization keys used in this module.
+pub const LOCALE_KEYS: &[&str] = &["notify.matured", "notify.hatched"];
+
+/// Compiled in, like the director's rules.
+const CURVES: &str = include_str!("../assets/growth.ron");
+
+/// A value that changes with age. See `assets/growth.ron`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(transparent)]
+struct GrowthCurve(Vec<(f32, f32)>);
+
+impl GrowthCurve {
+    fn sample(&self, t: f32) -> f32 {
+        let keys = &self.0;
+        let Some(first) = keys.first() else {
+            return 0.0;
+        };
+        if t <= first.0 {
+            return first.1;
+        }
+        for pair in keys.windows(2) {
+            let ((t0, v0), (t1, v1)) = (pair[0], pair[1]);
+            if t <= t1 {
+                return v0.lerp(v1, (t - t0) / (t1 - t0).max(f32::EPSILON));
+            }
+        }
+        keys[keys.len() - 1].1
+    }
+}
+
+#[derive(Resource, Debug, Clone, Deserialize)]
+struct GrowthCurves {
+    maturity_age: f32,
+    scale: GrowthCurve,
+    speed: GrowthCurve,
+    independence: GrowthCurve,
+    sight: GrowthCurve,
+    hearing: GrowthCurve,
+    brood_delay: f32,
+    brood_size: (usize, usize),
+    lake_capacity: usize,
+}
+
+/// Unlike the director's rules there's nothing sensible to fall back on.
+fn load_curves() -> GrowthCurves {
+    ron::from_str(CURVES).unwrap_or_else(|e| panic!("Failed to parse growth curves: {}", e))
+}
+
+/// How grown up a duckling is. The rest is what it was born with, which
+/// the curves scale.
+#[derive(Component, Debug, Clone)]
+pub struct Growth {
+    /// Seconds since hatching.
+    pub age: f32,
+    pub size: f32,
+    pub speed: f32,
+    /// In [0, 1]; see `assets/growth.ron`.
+    pub independence: f32,
+}
+
+impl Growth {
+    /// A newly hatched duckling of the given size and speed.
+    pub fn hatchling(scale: f32, speed: f32) -> Self {
+        Self {
+            age: 0.0,
+            size: scale,
+            speed,
+            independence: 0.0,
+        }
+    }
+}
+
+/// A duckling that has grown into an adult. It still counts as one of the
+/// level's ducklings, and as reunited if it found its parent first.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct GrownUp {
+    pub reunited: bool,
+}
+
+/// A grown-up whose own ducklings are on the way.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Nesting {
+    /// Seconds until they hatch.
+    pub left: f32,
+}
+
+/// A duckling hatched on the lake to a grown-up, rather than one the level
+/// started with. It's born home, and counts for nothing.
+#[derive(Component, Debug)]
+pub struct Hatched;
+
+/// Every duckling the level started with, grown up or not.
+#[derive(SystemParam)]
+pub struct Brood<'w, 's> {
+    ducklings: Query<
+        'w,
+        's,
+        (Has<Done>, Option<&'static GrownUp>),
+        (Or<(With<Duckling>, With<GrownUp>)>, Without<Hatched>),
+    >,
+}
+
+impl Brood<'_, '_> {
+    pub fn total(&self) -> usize {
+        self.ducklings.iter().count()
+    }
+
+    /// Found their parents, whether they've grown up since or not.
+    pub fn reunited(&self) -> usize {
+        self.ducklings
+            .iter()
+            .filter(|(done, grown)| *done || grown.is_some_and(|g| g.reunited))
+            .count()
+    }
+
+    /// Whether there's nobody left to reunite, as every duckling is either
+    /// home or grown up.
+    pub fn all_settled(&self) -> bool {
+        self.total() > 0
+            && self
+                .ducklings
+                .iter()
+                .all(|(done, grown)| done || grown.is_some())
+    }
+}
+
+fn grow_ducklings(
+    curves: Res<GrowthCurves>,
+    time: Res<Time<Fixed>>,
+    ducklings: Query<(
+        &mut Growth,
+        &mut Transform,
+        &mut Duck,
+        Option<&mut Perception>,
+    )>,
+) {
+    let dt = time.delta_secs();
+    let hatchling = curves.scale.sample(0.0);
+
+    for (mut growth, mut tf, mut duck, perception) in ducklings {
+        growth.age += dt;
+        let t = growth.age / curves.maturity_age;
+
+        let scale = growth.size * curves.scale.sample(t) / hatchling;
+        tf.scale = Vec3::splat(scale);
+        duck.speed_mod = growth.speed * curves.speed.sample(t);
+        growth.independence = curves.independence.sample(t).clamp(0.0, 1.0);
+
+        if let Some(mut perception) = perception {
+            perception.set_ranges(curves.sight.sample(t), curves.hearing.sample(t));
+        }
+    }
+}
+
+/// Grown ducklings strike out on their own as adults, in colours of their
+/// own, and settle down to nest.
+fn mature_ducklings(
+    mut commands: Commands,
+    curves: Res<GrowthCurves>,
+    ducklings: Query<(Entity, &Growth, Has<Done>)>,
+) {
+    for (duck, growth, done) in ducklings {
+        if growth.age < curves.maturity_age {
+            continue;
+        }
+
+        info!("Duckling {} has grown up", duck);
+        commands
+            .entity(duck)
+            // first, so leaving whoever it followed doesn't count as losing it
+            .remove::<Duckling>()
+            .remove::<(Following, DuckParent, Done, Growth)>()
+            .insert((
+                GrownUp { reunited: done },
+                Mind::new(false),
+                Perception::adult(),
+                FamilyColor::random(),
+                Voice::random(),
+                Nesting {
+                    left: curves.brood_delay,
+                },
+            ));
+        commands.trigger(SpawnScoreMarker { duck });
+        commands.trigger(Notification::new("notify.matured"));
+    }
+}
+
+/// Nesting grown-ups hatch broods of their own, which start out home with
+/// them. The lake only holds so many ducks, so late broods may be small, or
+/// not come at all.
+fn hatch_broods(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    curves: Res<GrowthCurves>,
+    time: Res<Time<Fixed>>,
+    nests: Query<(Entity, &mut Nesting, &Transform)>,
+    ducks: Query<(), With<Duck>>,
+) {
+    let mut room = curves.lake_capacity.saturating_sub(ducks.iter().count());
+
+    for (parent, mut nesting, tf) in nests {
+        nesting.left -= time.delta_secs();
+        if nesting.left > 0.0 {
+            continue;
+        }
+        commands.entity(parent).remove::<Nesting>();
+
+        let (fewest, most) = curves.brood_size;
+        let size = rng.range(fewest..=most).min(room);
+        room -= size;
+        if size == 0 {
+            continue;
+        }
+
+        for _ in 0..size {
+            let offset = rng.vec(1.0, 3.0);
+            let transform = Transform::from_translation(
+                tf.translation.with_y(0.0) + Vec3::new(offset.x, 0.0, offset.y),
+            )
+            .with_rotation(tf.rotation)
+            .with_scale(Vec3::splat(rng.range(0.2..0.3)));
+
+            let duckling = commands.spawn_empty().id();
+            commands.trigger(AddDuck {
+                transform,
+                is_player: false,
+                is_child: true,
+                entity: Some(duckling),
+            });
+            commands.entity(duckling).insert((
+                Hatched,
+                DuckParent(parent),
+                Following(parent),
+                Done,
+            ));
+        }
+
+        info!("Duck {} hatched {} ducklings", parent, size);
+        commands.trigger(Notification::new("notify.hatched"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ducks::on_add_duck;
+    use crate::hud::GameClock;
+    use crate::score::{Score, score_reunion};
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn growth_curves_parse() {
+        let curves = load_curves();
+        assert!(curves.maturity_age > 0.0);
+    }
+
+    #[test]
+    fn curves_hold_their_ends_and_interpolate_between() {
+        let curve = GrowthCurve(vec![(0.0, 1.0), (1.0, 3.0), (1.0, 5.0), (2.0, 5.0)]);
+        assert_eq!(curve.sample(-1.0), 1.0);
+        assert_eq!(curve.sample(0.0), 1.0);
+        assert_eq!(curve.sample(0.5), 2.0);
+        assert_eq!(curve.sample(1.5), 5.0);
+        assert_eq!(curve.sample(9.0), 5.0);
+        assert!(curve.sample(1.0).is_finite());
+        assert_eq!(GrowthCurve(Vec::new()).sample(0.5), 0.0);
+    }
+
+    #[test]
+    fn grown_up_ducklings_still_count_for_the_level() {
+        let mut app = App::new();
+        app.add_systems(Update, mature_ducklings);
+        let curves = load_curves();
+        let grown = Growth {
+            age: curves.maturity_age,
+            ..Growth::hatchling(0.25, 1.5)
+        };
+        app.insert_resource(curves);
+
+        let world = app.world_mut();
+        let home = world.spawn((Duckling, Done, grown.clone())).id();
+        let lost = world.spawn((Duckling, grown)).id();
+        let young = world.spawn((Duckling, Growth::hatchling(0.25, 1.5))).id();
+
+        let brood = |app: &mut App| {
+            app.world_mut()
+                .run_system_once(|brood: Brood| {
+                    (brood.total(), brood.reunited(), brood.all_settled())
+                })
+                .unwrap()
+        };
+        assert_eq!(brood(&mut app), (3, 1, false));
+
+        app.update();
+        let world = app.world();
+        assert!(!world.entity(home).contains::<Duckling>());
+        assert_eq!(
+            world.get::<GrownUp>(home),
+            Some(&GrownUp { reunited: true })
+        );
+        assert_eq!(
+            world.get::<GrownUp>(lost),
+            Some(&GrownUp { reunited: false })
+        );
+        assert!(world.entity(young).contains::<Duckling>());
+        assert_eq!(brood(&mut app), (3, 1, false));
+
+        // the last one home settles the level, though one never made it
+        app.world_mut().entity_mut(young).insert(Done);
+        assert_eq!(brood(&mut app), (3, 2, true));
+    }
+
+    #[test]
+    fn grown_ups_hatch_broods_while_the_lake_has_room() {
+        let mut app = App::new();
+        app.add_systems(Update, hatch_broods);
+        app.add_observer(on_add_duck);
+        app.add_observer(score_reunion);
+        app.init_resource::<GameRng>();
+        app.init_resource::<Time<Fixed>>();
+        app.init_resource::<GameClock>();
+        app.init_resource::<Score>();
+        app.init_resource::<Assets<Mesh>>();
+        app.init_resource::<Assets<StandardMaterial>>();
+        // room for one brood of two, and one duckling of the next
+        app.insert_resource(GrowthCurves {
+            brood_size: (2, 2),
+            lake_capacity: 6,
+            ..load_curves()
+        });
+
+        let world = app.world_mut();
+        let mut grown_up = |x: f32, left: f32| {
+            world
+                .spawn((
+                    Duck::default(),
+                    GrownUp { reunited: false },
+                    Nesting { left },
+                    Transform::from_xyz(x, 0.0, 0.0),
+                ))
+                .id()
+        };
+        let first = grown_up(0.0, 0.0);
+        let second = grown_up(20.0, 0.0);
+        let waiting = grown_up(40.0, 60.0);
+        app.update();
+
+        let world = app.world_mut();
+        let mut ducklings = world.query_filtered::<(&DuckParent, &Following), (
+            With<Duckling>,
+            With<Hatched>,
+            With<Done>,
+        )>();
+        let broods: Vec<(Entity, Entity)> =
+            ducklings.iter(world).map(|(p, f)| (p.0, f.0)).collect();
+        let brood_of = |parent| {
+            broods
+                .iter()
+                .filter(|(p, f)| *p == parent && *f == parent)
+                .count()
+        };
+        assert_eq!(broods.len(), 3);
+        assert_eq!(brood_of(first) + brood_of(second), 3);
+        assert!(!world.entity(first).contains::<Nesting>());
+        assert!(!world.entity(second).contains::<Nesting>());
+        assert!(world.entity(waiting).contains::<Nesting>());
+
+        // born home, so nothing to score and nobody new to find
+        assert_eq!(world.resource::<Score>().reunions, 0);
+        let brood = world
+            .run_system_once(|brood: Brood| (brood.total(), brood.all_settled()))
+            .unwrap();
+        assert_eq!(brood, (3, true));
+    }
+}
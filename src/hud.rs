@@ -0,0 +1,359 @@
+use bevy::color::palettes::tailwind::*;
+use bevy::prelude::*;
+use bevy::time::Stopwatch;
+
+use crate::child_rel::FollowedBy;
+use crate::growth::Brood;
+use crate::level::StartLevel;
+use crate::locale::Localization;
+use crate::player::{PlayerDuck, Stamina};
+use crate::score::Score;
+use crate::ui::{UiElement, UiFont, UiState};
+use crate::weather::{LightningEvent, Weather};
+
+pub fn hud_plugin(app: &mut App) {
+    app.add_systems(OnEnter(UiState::Game), spawn_hud);
+    app.add_systems(FixedUpdate, tick_game_clock.run_if(in_state(UiState::Game)));
+    app.add_systems(
+        Update,
+        (notify_weather_change, update_hud, fade_notifications)
+            .chain()
+            .run_if(in_state(UiState::Game)),
+    );
+
+    app.add_observer(on_notification);
+    app.add_observer(notify_lightning);
+    app.add_observer(reset_game_clock);
+
+    app.insert_resource(GameClock::default());
+}
+
+/// Localization keys used here, besides [`weather_key`]'s.
+pub const LOCALE_KEYS: &[&str] = &[
+    "notify.weather",
+    "notify.lightning",
+    "hud.reunited",
+    "hud.followers",
+    "hud.stamina",
+    "hud.score",
+];
+
+/// How long a notification stays in the feed, in seconds.
+const NOTIFICATION_TIME: f32 = 4.0;
+const MAX_NOTIFICATIONS: usize = 5;
+
+const STAMINA_BAR_WIDTH: f32 = 160.0;
+
+/// Time spent playing the current game.
+#[derive(Resource, Debug, Default)]
+pub struct GameClock(pub Stopwatch);
+
+/// Shows a line of text in the HUD's notification feed for a few seconds.
+#[derive(Event, Debug)]
+pub struct Notification {
+    pub key: &'static str,
+    /// Another localization key, filled in for `{detail}`.
+    pub detail: Option<&'static str>,
+}
+
+impl Notification {
+    pub fn new(key: &'static str) -> Self {
+        Self { key, detail: None }
+    }
+}
+
+#[derive(Component)]
+enum HudText {
+    Score,
+    Reunited,
+    Followers,
+    Clock,
+    Weather,
+    Stamina,
+}
+
+#[derive(Component)]
+struct WeatherIcon;
+
+#[derive(Component)]
+struct StaminaBar;
+
+#[derive(Component)]
+struct NotificationFeed;
+
+#[derive(Component)]
+struct NotificationEntry {
+    age: f32,
+}
+
+pub fn weather_key(weather: Weather) -> &'static str {
+    match weather {
+        Weather::Clear => "weather.clear",
+        Weather::Fog => "weather.fog",
+        Weather::Thunderstorm => "weather.thunderstorm",
+    }
+}
+
+fn weather_color(weather: Weather) -> Srgba {
+    match weather {
+        Weather::Clear => AMBER_300,
+        Weather::Fog => SLATE_300,
+        Weather::Thunderstorm => INDIGO_500,
+    }
+}
+
+fn spawn_hud(mut commands: Commands, font: Res<UiFont>) {
+    let text = |size: f32| TextFont {
+        font: font.0.clone(),
+        font_size: size,
+        ..default()
+    };
+
+    let panel = Node {
+        flex_direction: FlexDirection::Column,
+        padding: UiRect::all(px(10.0)),
+        row_gap: px(4.0),
+        border_radius: BorderRadius::all(px(8.0)),
+        ..default()
+    };
+    let panel_color = BackgroundColor(ZINC_950.with_alpha(0.4).into());
+
+    // counters, top left
+    commands
+        .spawn((
+            UiElement,
+            Node {
+                position_type: PositionType::Absolute,
+                left: px(16.0),
+                top: px(16.0),
+                ..panel.clone()
+            },
+            panel_color,
+        ))
+        .with_child((HudText::Score, Text::default(), text(26.0)))
+        .with_child((HudText::Reunited, Text::default(), text(18.0)))
+        .with_child((HudText::Followers, Text::default(), text(18.0)))
+        .with_child((HudText::Clock, Text::default(), text(18.0)));
+
+    // weather, top center
+    commands
+        .spawn((
+            UiElement,
+            Node {
+                position_type: PositionType::Absolute,
+                top: px(16.0),
+                width: percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        column_gap: px(8.0),
+                        ..panel.clone()
+                    },
+                    panel_color,
+                ))
+                .with_child((
+                    WeatherIcon,
+                    Node {
+                        width: px(18.0),
+                        height: px(18.0),
+                        border_radius: BorderRadius::MAX,
+                        ..default()
+                    },
+                ))
+                .with_child((HudText::Weather, Text::default(), text(18.0)));
+        });
+
+    // stamina, bottom left
+    commands
+        .spawn((
+            UiElement,
+            Node {
+                position_type: PositionType::Absolute,
+                left: px(16.0),
+                bottom: px(16.0),
+                ..panel.clone()
+            },
+            panel_color,
+        ))
+        .with_child((HudText::Stamina, Text::default(), text(16.0)))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        width: px(STAMINA_BAR_WIDTH),
+                        height: px(10.0),
+                        border_radius: BorderRadius::all(px(5.0)),
+                        ..default()
+                    },
+                    BackgroundColor(ZINC_800.into()),
+                ))
+                .with_child((
+                    StaminaBar,
+                    Node {
+                        width: percent(100.0),
+                        height: percent(100.0),
+                        border_radius: BorderRadius::all(px(5.0)),
+                        ..default()
+                    },
+                    BackgroundColor(LIME_400.into()),
+                ));
+        });
+
+    // notifications, top right
+    commands.spawn((
+        UiElement,
+        NotificationFeed,
+        Node {
+            position_type: PositionType::Absolute,
+            right: px(16.0),
+            top: px(16.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::End,
+            row_gap: px(4.0),
+            ..default()
+        },
+    ));
+}
+
+fn reset_game_clock(_event: On<StartLevel>, mut clock: ResMut<GameClock>) {
+    clock.0.reset();
+}
+
+fn tick_game_clock(mut clock: ResMut<GameClock>, time: Res<Time>) {
+    clock.0.tick(time.delta());
+}
+
+fn notify_weather_change(mut commands: Commands, weather: Res<State<Weather>>) {
+    if weather.is_changed() && !weather.is_added() {
+        commands.trigger(Notification {
+            key: "notify.weather",
+            detail: Some(weather_key(**weather)),
+        });
+    }
+}
+
+/// Only when the player is leading ducklings, who panic at the thunder.
+fn notify_lightning(
+    _event: On<LightningEvent>,
+    mut commands: Commands,
+    leading: Query<(), (With<PlayerDuck>, With<FollowedBy>)>,
+) {
+    if !leading.is_empty() {
+        commands.trigger(Notification::new("notify.lightning"));
+    }
+}
+
+fn update_hud(
+    loc: Res<Localization>,
+    clock: Res<GameClock>,
+    score: Res<Score>,
+    weather: Res<State<Weather>>,
+    brood: Brood,
+    player: Single<(&Stamina, Option<&FollowedBy>), With<PlayerDuck>>,
+    texts: Query<(&mut Text, &HudText)>,
+    mut icon: Single<&mut BackgroundColor, (With<WeatherIcon>, Without<StaminaBar>)>,
+    mut bar: Single<(&mut Node, &mut BackgroundColor), With<StaminaBar>>,
+) {
+    let (stamina, followers) = *player;
+    let total = brood.total();
+    let reunited = brood.reunited();
+    let n_followers = followers.map(|f| f.len()).unwrap_or(0);
+    let secs = clock.0.elapsed_secs() as u32;
+
+    for (mut text, which) in texts {
+        let s = match which {
+            HudText::Score => loc.format("hud.score", &[("score", &score.total())]),
+            HudText::Reunited => loc.format(
+                "hud.reunited",
+                &[("reunited", &reunited), ("total", &total)],
+            ),
+            HudText::Followers => loc.format("hud.followers", &[("followers", &n_followers)]),
+            HudText::Clock => format!("{}:{:02}", secs / 60, secs % 60),
+            HudText::Weather => loc.get(weather_key(**weather)).to_owned(),
+            HudText::Stamina => loc.get("hud.stamina").to_owned(),
+        };
+        if text.0 != s {
+            text.0 = s;
+        }
+    }
+
+    icon.0 = weather_color(**weather).into();
+
+    let (node, color) = &mut *bar;
+    node.width = percent(stamina.0 * 100.0);
+    color.0 = if stamina.0 < 0.25 { RED_500 } else { LIME_400 }.into();
+}
+
+fn on_notification(
+    event: On<Notification>,
+    mut commands: Commands,
+    loc: Res<Localization>,
+    font: Res<UiFont>,
+    feed: Query<Entity, With<NotificationFeed>>,
+    entries: Query<(Entity, &NotificationEntry)>,
+) {
+    let Ok(feed) = feed.single() else {
+        return;
+    };
+
+    let text = match event.detail {
+        Some(detail) => loc.format(event.key, &[("detail", &loc.get(detail))]),
+        None => loc.get(event.key).to_owned(),
+    };
+
+    // make room by dropping the oldest
+    let mut existing: Vec<_> = entries.iter().collect();
+    existing.sort_by(|a, b| b.1.age.total_cmp(&a.1.age));
+    let n_drop = (existing.len() + 1).saturating_sub(MAX_NOTIFICATIONS);
+    for (e, _) in existing.into_iter().take(n_drop) {
+        commands.entity(e).despawn();
+    }
+
+    commands.spawn((
+        NotificationEntry { age: 0.0 },
+        Node {
+            padding: UiRect::axes(px(10.0), px(4.0)),
+            border_radius: BorderRadius::all(px(6.0)),
+            ..default()
+        },
+        BackgroundColor(ZINC_950.with_alpha(0.5).into()),
+        Text::new(text),
+        TextFont {
+            font: font.0.clone(),
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        ChildOf(feed),
+    ));
+}
+
+fn fade_notifications(
+    mut commands: Commands,
+    entries: Query<(
+        Entity,
+        &mut NotificationEntry,
+        &mut TextColor,
+        &mut BackgroundColor,
+    )>,
+    time: Res<Time>,
+) {
+    for (e, mut entry, mut color, mut bg) in entries {
+        entry.age += time.delta_secs();
+        if entry.age >= NOTIFICATION_TIME {
+            commands.entity(e).despawn();
+            continue;
+        }
+
+        let alpha = ((NOTIFICATION_TIME - entry.age) / 0.5).min(1.0);
+        color.0.set_alpha(alpha);
+        bg.0.set_alpha(0.5 * alpha);
+    }
+}
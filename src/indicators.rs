@@ -0,0 +1,282 @@
+use bevy::color::palettes::tailwind::*;
+use bevy::prelude::*;
+
+use crate::child_rel::{DuckParent, Following};
+use crate::ducks::{Done, Duckling, FamilyColor};
+use crate::locale::Localization;
+use crate::player::PlayerDuck;
+use crate::text_bubble::Quack;
+use crate::ui::UiFont;
+
+pub fn indicators_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            spawn_indicators,
+            toggle_parent_highlight_on_h,
+            pulse_indicators_on_quack,
+            update_indicators,
+        )
+            .chain(),
+    );
+
+    app.insert_resource(IndicatorSettings {
+        highlight_parents: true,
+    });
+}
+
+/// Localization keys used in this module.
+pub const LOCALE_KEYS: &[&str] = &["indicator.distance"];
+
+/// Edge indicators are kept this far inside the window, in pixels.
+const EDGE_MARGIN: f32 = 40.0;
+
+const INDICATOR_SIZE: f32 = 22.0;
+const ARROW_SIZE: f32 = 10.0;
+
+/// How fast a quack pulse dies down, per second.
+const PULSE_DECAY: f32 = 3.0;
+
+#[derive(Resource, Debug)]
+pub struct IndicatorSettings {
+    /// While the player leads ducklings, point out their true parents.
+    pub highlight_parents: bool,
+}
+
+#[derive(Component, Debug)]
+#[relationship(relationship_target = IndicatedBy)]
+struct Indicates(Entity);
+
+#[derive(Component, Debug)]
+#[relationship_target(relationship = Indicates, linked_spawn)]
+struct IndicatedBy(Entity);
+
+/// A screen-space marker for a duck. Ducklings get one while they're off
+/// screen; family adults while the player is leading one of their children.
+#[derive(Component, Debug)]
+struct Indicator {
+    arrow: Entity,
+    label: Entity,
+    pulse: f32,
+}
+
+fn spawn_indicators(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    targets: Query<
+        Entity,
+        (
+            Or<(With<Duckling>, With<FamilyColor>)>,
+            Without<IndicatedBy>,
+        ),
+    >,
+) {
+    for target in targets {
+        let arrow = commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: px(ARROW_SIZE),
+                    height: px(ARROW_SIZE),
+                    left: px((INDICATOR_SIZE - ARROW_SIZE) / 2.0),
+                    top: px((INDICATOR_SIZE - ARROW_SIZE) / 2.0),
+                    ..default()
+                },
+                BackgroundColor(Color::WHITE),
+            ))
+            .id();
+
+        let label = commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: px(INDICATOR_SIZE + 2.0),
+                    ..default()
+                },
+                Text::default(),
+                TextFont {
+                    font: font.0.clone(),
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ))
+            .id();
+
+        commands
+            .spawn((
+                Indicates(target),
+                Indicator {
+                    arrow,
+                    label,
+                    pulse: 0.0,
+                },
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: px(INDICATOR_SIZE),
+                    height: px(INDICATOR_SIZE),
+                    border_radius: BorderRadius::MAX,
+                    ..default()
+                },
+                BackgroundColor(Color::WHITE),
+                Outline::default(),
+                Visibility::Hidden,
+            ))
+            .add_children(&[arrow, label]);
+    }
+}
+
+fn toggle_parent_highlight_on_h(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<IndicatorSettings>,
+) {
+    if keys.just_pressed(KeyCode::KeyH) {
+        settings.highlight_parents = !settings.highlight_parents;
+        info!("Highlight parents: {}", settings.highlight_parents);
+    }
+}
+
+fn pulse_indicators_on_quack(
+    mut messages: MessageReader<Quack>,
+    indicated: Query<&IndicatedBy>,
+    mut indicators: Query<&mut Indicator>,
+) {
+    for quack in messages.read() {
+        let Ok(by) = indicated.get(quack.entity) else {
+            continue;
+        };
+        if let Ok(mut indicator) = indicators.get_mut(by.0) {
+            indicator.pulse = 1.0;
+        }
+    }
+}
+
+/// Where on the window edge to put a marker for a point in view space.
+fn edge_position(view_pos: Vec3, viewport: Vec2) -> (Vec2, Vec2) {
+    // the view-space direction is right even for points behind the camera,
+    // where projecting would mirror them
+    let dir = Vec2::new(view_pos.x, -view_pos.y)
+        .try_normalize()
+        .unwrap_or(Vec2::Y);
+
+    let half = (viewport / 2.0 - EDGE_MARGIN).max(Vec2::ZERO);
+    let t = (half.x / dir.x.abs()).min(half.y / dir.y.abs());
+    (viewport / 2.0 + dir * t, dir)
+}
+
+fn update_indicators(
+    settings: Res<IndicatorSettings>,
+    loc: Res<Localization>,
+    time: Res<Time>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera3d>>,
+    ui_scale: Res<UiScale>,
+    player: Single<(Entity, &Transform), With<PlayerDuck>>,
+    led: Query<(&Following, &DuckParent)>,
+    targets: Query<(&Transform, Has<Duckling>, Has<Done>, Option<&DuckParent>)>,
+    families: Query<&FamilyColor>,
+    indicators: Query<(
+        &Indicates,
+        &mut Indicator,
+        &mut Node,
+        &mut Visibility,
+        &mut BackgroundColor,
+        &mut UiTransform,
+        &mut Outline,
+    )>,
+    mut arrows: Query<(&mut UiTransform, &mut Visibility), Without<Indicator>>,
+    mut labels: Query<&mut Text>,
+) {
+    let (camera, camera_tf) = *camera;
+    let (player, player_tf) = *player;
+    let Some(viewport) = camera.logical_viewport_size() else {
+        return;
+    };
+    let world_to_view = camera_tf.affine().inverse();
+
+    let led_parents: Vec<Entity> = led
+        .iter()
+        .filter(|(f, _)| f.0 == player)
+        .map(|(_, p)| p.0)
+        .collect();
+
+    for (target, mut indicator, mut node, mut vis, mut bg, mut ui_tf, mut outline) in indicators {
+        indicator.pulse = (indicator.pulse - PULSE_DECAY * time.delta_secs()).max(0.0);
+
+        let Ok((tf, is_duckling, is_done, true_parent)) = targets.get(target.0) else {
+            continue;
+        };
+
+        let (wanted, color) = if is_duckling {
+            let color = true_parent
+                .and_then(|p| families.get(p.0).ok())
+                .map(|c| c.0)
+                .unwrap_or(GRAY_400.into());
+            (!is_done, color)
+        } else {
+            let wanted = settings.highlight_parents && led_parents.contains(&target.0);
+            let color = families.get(target.0).map(|c| c.0).unwrap_or(Color::WHITE);
+            (wanted, color)
+        };
+
+        let world_pos = tf.translation + Vec3::Y * 2.0 * tf.scale.y;
+        let view_pos = world_to_view.transform_point3(world_pos);
+        let on_screen = camera
+            .world_to_viewport(camera_tf, world_pos)
+            .ok()
+            .filter(|p| view_pos.z < 0.0 && p.cmpge(Vec2::ZERO).all() && p.cmple(viewport).all());
+
+        // ducklings are only pointed at off screen; parents are marked either way
+        let (pos, dir) = match on_screen {
+            Some(_) if is_duckling => {
+                vis.set_if_neq(Visibility::Hidden);
+                continue;
+            }
+            Some(p) => (p, None),
+            None => {
+                let (p, dir) = edge_position(view_pos, viewport);
+                (p, Some(dir))
+            }
+        };
+
+        if !wanted {
+            vis.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+        vis.set_if_neq(Visibility::Inherited);
+
+        // UI lengths are scaled with the window, screen positions aren't
+        let pos = pos / ui_scale.0;
+        node.left = px(pos.x - INDICATOR_SIZE / 2.0);
+        node.top = px(pos.y - INDICATOR_SIZE / 2.0);
+        bg.0 = color;
+        ui_tf.scale = Vec2::splat(1.0 + 0.6 * indicator.pulse);
+
+        *outline = if is_duckling {
+            Outline::default()
+        } else {
+            Outline::new(px(3.0), px(2.0), AMBER_200.into())
+        };
+
+        if let Ok((mut arrow_tf, mut arrow_vis)) = arrows.get_mut(indicator.arrow) {
+            match dir {
+                Some(dir) => {
+                    arrow_vis.set_if_neq(Visibility::Inherited);
+                    let offset = dir * INDICATOR_SIZE * 0.6;
+                    arrow_tf.translation = Val2::px(offset.x, offset.y);
+                    arrow_tf.rotation = Rot2::radians(dir.to_angle() + std::f32::consts::FRAC_PI_4);
+                }
+                None => {
+                    arrow_vis.set_if_neq(Visibility::Hidden);
+                }
+            }
+        }
+
+        if let Ok(mut text) = labels.get_mut(indicator.label) {
+            let distance = tf.translation.distance(player_tf.translation).round() as i32;
+            let s = loc.format("indicator.distance", &[("distance", &distance)]);
+            if text.0 != s {
+                text.0 = s;
+            }
+        }
+    }
+}
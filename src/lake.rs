@@ -2,18 +2,43 @@ use bevy::color::palettes::tailwind::*;
 use bevy::prelude::*;
 use std::collections::BTreeMap;
 
-use crate::math::random_vec;
+use crate::level::PopulateLevel;
+use crate::math::{GameRng, cosmetic_random_range, cosmetic_random_vec};
+use crate::navigation::Obstacle;
 
 pub fn lake_plugin(app: &mut App) {
     app.add_systems(Startup, (setup_resources, add_lake_cells).chain());
     // app.add_systems(FixedUpdate, update_cell_heights);
+    app.add_systems(FixedUpdate, drift_logs);
 
     app.add_observer(on_add_lake_cell);
+    app.add_observer(spawn_logs);
 }
 
 #[derive(Component)]
 struct LakeCell;
 
+/// A clump of reeds near the shore that ducks can shelter in.
+#[derive(Component)]
+pub struct ReedBed;
+
+const NUM_REED_BEDS: usize = 8;
+
+/// A log floating about the lake, in the ducks' way.
+#[derive(Component, Debug)]
+pub struct DriftingLog {
+    velocity: Vec3,
+}
+
+const NUM_LOGS: usize = 6;
+const LOG_LENGTH: f32 = 6.0;
+
+/// Metres per second.
+const LOG_DRIFT_SPEED: f32 = 0.4;
+
+/// Logs turn back toward the middle past this distance from it.
+const LOG_RANGE: f32 = LAKE_RADIUS * 0.8;
+
 #[allow(unused)]
 pub struct LakeIndex(pub IVec2);
 
@@ -80,7 +105,7 @@ fn setup_resources(
     let lillypad_material = materials.add(StandardMaterial::from_color(GREEN_500));
 
     for _ in 0..200 {
-        let pos = random_vec(0.0, LAKE_RADIUS);
+        let pos = cosmetic_random_vec(0.0, LAKE_RADIUS);
         let tf = Transform::from_xyz(pos.x, 0.0, pos.y);
         commands.spawn((
             tf,
@@ -88,6 +113,26 @@ fn setup_resources(
             MeshMaterial3d(lillypad_material.clone()),
         ));
     }
+
+    let reed = meshes.add(Cylinder::new(0.05, 3.0));
+    let reed_material = materials.add(StandardMaterial::from_color(GREEN_800));
+
+    for i in 0..NUM_REED_BEDS {
+        let a = i as f32 / NUM_REED_BEDS as f32 * std::f32::consts::PI * 2.0;
+        let r = LAKE_RADIUS * 0.9;
+        let tf = Transform::from_xyz(r * a.cos(), 0.0, r * a.sin());
+
+        let mut bed = commands.spawn((tf, ReedBed, InheritedVisibility::VISIBLE));
+        for _ in 0..40 {
+            let p = cosmetic_random_vec(0.0, 6.0);
+            let h = cosmetic_random_range(0.8..1.4);
+            bed.with_child((
+                Transform::from_xyz(p.x, 1.5 * h, p.y).with_scale(Vec3::new(1.0, h, 1.0)),
+                Mesh3d(reed.clone()),
+                MeshMaterial3d(reed_material.clone()),
+            ));
+        }
+    }
 }
 
 fn add_lake_cells(mut commands: Commands) {
@@ -95,3 +140,59 @@ fn add_lake_cells(mut commands: Commands) {
         location: (0, 0).into(),
     });
 }
+
+fn spawn_logs(
+    _event: On<PopulateLevel>,
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    existing: Query<Entity, With<DriftingLog>>,
+) {
+    for e in existing {
+        commands.entity(e).despawn();
+    }
+
+    let mesh = meshes.add(Capsule3d::new(0.4, LOG_LENGTH));
+    let material = materials.add(StandardMaterial::from_color(AMBER_900));
+    let horizontal = Quat::from_rotation_x(std::f32::consts::PI / 2.0);
+
+    for _ in 0..NUM_LOGS {
+        let p = rng.vec(20.0, LOG_RANGE);
+        let v = rng.vec(0.0, LOG_DRIFT_SPEED);
+        let tf = Transform::from_xyz(p.x, 0.0, p.y)
+            .with_rotation(Quat::from_rotation_y(rng.range(0.0..std::f32::consts::TAU)));
+        commands.spawn((
+            DriftingLog {
+                velocity: Vec3::new(v.x, 0.0, v.y),
+            },
+            Obstacle {
+                radius: LOG_LENGTH / 2.0,
+            },
+            tf,
+            Visibility::default(),
+            children![(
+                Transform::from_rotation(horizontal),
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(material.clone()),
+            )],
+        ));
+    }
+}
+
+fn drift_logs(
+    mut rng: ResMut<GameRng>,
+    time: Res<Time<Fixed>>,
+    logs: Query<(&mut Transform, &mut DriftingLog)>,
+) {
+    let dt = time.delta_secs();
+    for (mut tf, mut log) in logs {
+        if tf.translation.length() > LOG_RANGE {
+            log.velocity = -tf.translation.with_y(0.0).normalize() * LOG_DRIFT_SPEED;
+        } else if rng.chance(0.002) {
+            let v = rng.vec(0.0, LOG_DRIFT_SPEED);
+            log.velocity = Vec3::new(v.x, 0.0, v.y);
+        }
+        tf.translation += log.velocity * dt;
+    }
+}
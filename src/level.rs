@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+
+use crate::math::GameRng;
+use crate::weather::Weather;
+
+pub fn level_plugin(app: &mut App) {
+    app.add_systems(Startup, start_first_level);
+
+    app.add_observer(on_start_level);
+
+    app.insert_resource(Level::LAKE);
+    app.init_resource::<GameRng>();
+}
+
+/// The level being played. Leaderboards are kept per name and seed.
+#[derive(Resource, Debug, Clone)]
+pub struct Level {
+    pub name: &'static str,
+    pub seed: u64,
+}
+
+impl Level {
+    /// The same lake every time, so scores on it can be compared. Pass
+    /// `--seed <n>` for a different one.
+    pub const LAKE: Level = Level {
+        name: "lake",
+        seed: 0x00d0_c5ed,
+    };
+
+    /// Identifies this level and seed in file names.
+    pub fn key(&self) -> String {
+        format!("{}-{:016x}", self.name, self.seed)
+    }
+}
+
+/// (Re)starts the current [`Level`] from scratch. Anything that keeps
+/// per-game state should reset it when this fires.
+#[derive(Event, Debug)]
+pub struct StartLevel;
+
+/// Fired by [`StartLevel`] once the game RNG has been reseeded, for
+/// whatever populates the level.
+#[derive(Event, Debug)]
+pub struct PopulateLevel;
+
+fn start_first_level(mut commands: Commands) {
+    commands.trigger(StartLevel);
+}
+
+fn on_start_level(
+    _event: On<StartLevel>,
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    level: Res<Level>,
+    weather: Res<State<Weather>>,
+    mut next_weather: ResMut<NextState<Weather>>,
+) {
+    info!("Starting level {}", level.key());
+    rng.reseed(level.seed);
+    if **weather != Weather::Clear {
+        next_weather.set(Weather::Clear);
+    }
+    commands.trigger(PopulateLevel);
+}
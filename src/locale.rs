@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+
+use crate::camera::{self, CameraMode};
+use crate::quacks::QuackKind;
+use crate::weather::Weather;
+use crate::{
+    ducks, growth, hud, indicators, photo, predators, replay, savegame, score, text_bubble,
+};
+
+pub fn locale_plugin(app: &mut App) {
+    app.add_systems(Startup, report_missing_translations);
+    app.add_systems(Update, cycle_language_on_f2);
+
+    app.insert_resource(Localization::new(FALLBACK_LANGUAGE));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Language {
+    English,
+    French,
+}
+
+/// Used for any key the current language doesn't have.
+const FALLBACK_LANGUAGE: Language = Language::English;
+
+/// Every shipped locale. The tables are compiled in so they're available
+/// immediately on every platform, web included.
+const LOCALES: [(Language, &str); 2] = [
+    (Language::English, include_str!("../assets/locale/en.ron")),
+    (Language::French, include_str!("../assets/locale/fr.ron")),
+];
+
+type Table = BTreeMap<String, String>;
+
+/// Translated strings, keyed like `"quack.lost.1"`.
+#[derive(Resource, Debug)]
+pub struct Localization {
+    language: Language,
+    tables: BTreeMap<Language, Table>,
+}
+
+impl Localization {
+    pub fn new(language: Language) -> Self {
+        let tables = LOCALES
+            .iter()
+            .map(|(lang, src)| {
+                let table = ron::from_str::<Table>(src).unwrap_or_else(|e| {
+                    error!("Failed to parse {:?} locale: {}", lang, e);
+                    Table::new()
+                });
+                (*lang, table)
+            })
+            .collect();
+
+        Self { language, tables }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    pub fn set_language(&mut self, language: Language) {
+        info!("Set language: {:?}", language);
+        self.language = language;
+    }
+
+    fn lookup(&self, language: Language, key: &str) -> Option<&str> {
+        self.tables.get(&language)?.get(key).map(|s| s.as_str())
+    }
+
+    /// The string for `key`, falling back to English and then to the key
+    /// itself so a missing translation is obvious on screen.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.lookup(self.language, key)
+            .or_else(|| self.lookup(FALLBACK_LANGUAGE, key))
+            .unwrap_or(key)
+    }
+
+    /// Like [`Self::get`], with each `{name}` replaced by its value.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+        let mut s = self.get(key).to_owned();
+        for (name, value) in args {
+            s = s.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        s
+    }
+
+    /// Keys present in the fallback locale but missing from `language`.
+    pub fn missing_keys(&self, language: Language) -> Vec<&str> {
+        let Some(fallback) = self.tables.get(&FALLBACK_LANGUAGE) else {
+            return Vec::new();
+        };
+        fallback
+            .keys()
+            .filter(|key| self.lookup(language, key).is_none())
+            .map(|key| key.as_str())
+            .collect()
+    }
+}
+
+fn report_missing_translations(loc: Res<Localization>) {
+    for (language, _) in LOCALES {
+        for key in loc.missing_keys(language) {
+            warn!("{:?} locale is missing {:?}", language, key);
+        }
+        for key in keys_used_in_code() {
+            if loc.lookup(language, key).is_none() {
+                warn!("{:?} locale is missing {:?}, used in code", language, key);
+            }
+        }
+    }
+}
+
+/// Every key the game may look up. Each module lists the keys it uses, and
+/// the ones built at runtime come from the same tables the game picks them
+/// from.
+fn keys_used_in_code() -> Vec<&'static str> {
+    let mut keys = [
+        camera::LOCALE_KEYS,
+        ducks::LOCALE_KEYS,
+        growth::LOCALE_KEYS,
+        hud::LOCALE_KEYS,
+        indicators::LOCALE_KEYS,
+        photo::LOCALE_KEYS,
+        predators::LOCALE_KEYS,
+        replay::LOCALE_KEYS,
+        savegame::LOCALE_KEYS,
+        score::LOCALE_KEYS,
+        text_bubble::LOCALE_KEYS,
+    ]
+    .concat();
+    keys.extend(CameraMode::ALL.map(CameraMode::key));
+    keys.extend(Weather::ALL.map(hud::weather_key));
+    for kind in QuackKind::ALL {
+        keys.extend(kind.spec().texts);
+    }
+    keys
+}
+
+fn cycle_language_on_f2(keys: Res<ButtonInput<KeyCode>>, mut loc: ResMut<Localization>) {
+    if keys.just_pressed(KeyCode::F2) {
+        let next = match loc.language() {
+            Language::English => Language::French,
+            Language::French => Language::English,
+        };
+        loc.set_language(next);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_locale_covers_every_key() {
+        assert!(LOCALES.iter().any(|(lang, _)| *lang == FALLBACK_LANGUAGE));
+
+        let tables: BTreeMap<Language, Table> = LOCALES
+            .iter()
+            .map(|(lang, src)| match ron::from_str::<Table>(src) {
+                Ok(table) => (*lang, table),
+                Err(e) => panic!("{lang:?} locale doesn't parse: {e}"),
+            })
+            .collect();
+        let fallback = &tables[&FALLBACK_LANGUAGE];
+        let used = keys_used_in_code();
+
+        for (lang, table) in &tables {
+            for key in &used {
+                assert!(table.contains_key(*key), "{lang:?} is missing {key:?}");
+            }
+            for key in fallback.keys() {
+                assert!(table.contains_key(key), "{lang:?} is missing {key:?}");
+            }
+        }
+    }
+}
@@ -1,34 +1,124 @@
+use bevy::app::{PluginGroupBuilder, ScheduleRunnerPlugin};
 use bevy::asset::AssetMetaCheck;
+use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
 use bevy::prelude::*;
+use bevy::render::settings::WgpuSettings;
+use bevy::render::{RenderApp, RenderPlugin};
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
 use bevy_rich_text3d::{LoadFonts, Text3dPlugin};
+use bevy_vector_shapes::painter::PainterPlugin;
 use bevy_vector_shapes::prelude::*;
+use std::time::Duration;
 
+mod atmosphere;
+mod audio;
+mod behaviour;
+mod camera;
 mod child_rel;
 mod clouds;
 mod debug;
 mod despawn_after;
 mod detached_camera;
+mod director;
 mod ducks;
+mod growth;
+mod hud;
+mod indicators;
 mod lake;
+mod level;
+mod locale;
 mod math;
+mod minimap;
+mod navigation;
 mod particles;
+mod perception;
+mod persist;
+mod photo;
 mod player;
+mod predators;
+mod quacks;
+mod replay;
+mod savegame;
+mod score;
 mod test_scene;
 mod text_bubble;
 mod ui;
+mod voice;
 mod weather;
 
-fn main() {
+fn main() -> AppExit {
     let mut app = App::new();
 
-    app.add_plugins(DefaultPlugins.set(AssetPlugin {
+    // `--verify-replay <file>` plays a replay without a window, as fast as it
+    // can, and exits with an error if it doesn't end the way it was recorded
+    let args: Vec<String> = std::env::args().collect();
+    let verify_replay = args
+        .iter()
+        .position(|a| a == "--verify-replay")
+        .and_then(|i| args.get(i + 1).cloned());
+    // `--seed <n>` plays a different lake to the usual one
+    let seed = args
+        .iter()
+        .position(|a| a == "--seed")
+        .and_then(|i| args.get(i + 1)?.parse::<u64>().ok());
+
+    if verify_replay.is_some() {
+        app.add_plugins(default_plugins(true));
+        app.add_plugins(ScheduleRunnerPlugin::run_loop(Duration::ZERO));
+    } else {
+        app.add_plugins(default_plugins(false));
+    }
+    add_game_plugins(&mut app);
+
+    if let Some(seed) = seed {
+        app.insert_resource(level::Level {
+            seed,
+            ..level::Level::LAKE
+        });
+    }
+
+    if let Some(path) = verify_replay
+        && let Err(e) = replay::verify_replay(&mut app, &path)
+    {
+        error!("Can't verify {}: {}", path, e);
+        return AppExit::error();
+    }
+
+    app.run()
+}
+
+/// Without a window or a GPU when `headless`.
+fn default_plugins(headless: bool) -> PluginGroupBuilder {
+    let plugins = DefaultPlugins.set(AssetPlugin {
         // Wasm builds will check for meta files (that don't exist) if this isn't set.
         // This causes errors and even panics in web builds on itch.
         // See https://github.com/bevyengine/bevy_github_ci_template/issues/48.
         meta_check: AssetMetaCheck::Never,
         ..default()
-    }));
+    });
+    if !headless {
+        return plugins;
+    }
 
+    plugins
+        .set(WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            ..default()
+        })
+        .set(RenderPlugin {
+            render_creation: WgpuSettings {
+                backends: None,
+                ..default()
+            }
+            .into(),
+            ..default()
+        })
+        .disable::<WinitPlugin>()
+}
+
+fn add_game_plugins(app: &mut App) {
     app.add_plugins(Text3dPlugin {
         default_atlas_dimension: (2048, 2048),
         load_system_fonts: true,
@@ -41,20 +131,49 @@ fn main() {
         ..Default::default()
     });
 
-    app.add_plugins(ShapePlugin::default())
-        // .add_plugins(camera_plugin)
-        .add_plugins(player::player_plugin)
+    // once, here, since adding it twice panics; the clouds read it
+    app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+
+    // the shape pipelines need a RenderApp, which headless runs don't have;
+    // the painter alone is enough for the ripples to keep drawing into nothing
+    if app.get_sub_app(RenderApp).is_some() {
+        app.add_plugins(ShapePlugin::default());
+    } else {
+        app.insert_resource(BaseShapeConfig(ShapeConfig::default_3d()))
+            .add_plugins(PainterPlugin);
+    }
+
+    app.add_plugins(player::player_plugin)
+        .add_plugins(camera::camera_plugin)
+        .add_plugins(director::director_plugin)
+        .add_plugins(photo::photo_plugin)
         .add_plugins(ducks::player_plugin)
+        .add_plugins(behaviour::behaviour_plugin)
+        .add_plugins(perception::perception_plugin)
+        .add_plugins(navigation::navigation_plugin)
+        .add_plugins(growth::growth_plugin)
         .add_plugins(debug::debug_plugin)
         .add_plugins(lake::lake_plugin)
         .add_plugins(particles::particles_plugin)
         .add_plugins(clouds::clouds_plugin)
         .add_plugins(weather::weather_plugin)
+        .add_plugins(atmosphere::atmosphere_plugin)
         .add_plugins(text_bubble::text_bubble_plugin)
         .add_plugins(despawn_after::despawn_after_plugin)
+        .add_plugins(audio::audio_plugin)
+        .add_plugins(voice::voice_plugin)
+        .add_plugins(quacks::quacks_plugin)
+        .add_plugins(locale::locale_plugin)
         .add_plugins(ui::ui_plugin)
-        .add_systems(Startup, setup)
-        .run();
+        .add_plugins(hud::hud_plugin)
+        .add_plugins(level::level_plugin)
+        .add_plugins(score::score_plugin)
+        .add_plugins(savegame::savegame_plugin)
+        .add_plugins(replay::replay_plugin)
+        .add_plugins(indicators::indicators_plugin)
+        .add_plugins(minimap::minimap_plugin)
+        .add_plugins(predators::predators_plugin)
+        .add_systems(Startup, setup);
 }
 
 fn setup(mut commands: Commands) {
@@ -65,6 +184,7 @@ fn setup(mut commands: Commands) {
             ..default()
         }),
         SpatialListener::new(1.0),
+        DistanceFog::default(),
         Transform::from_xyz(12.0, 25.0, 8.0).looking_at(Vec3::ZERO, Vec3::Y),
     ));
 }
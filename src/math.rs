@@ -1,10 +1,50 @@
-use bevy::math::Vec2;
+use bevy::math::{Vec2, Vec3};
+use bevy::prelude::Resource;
 use rand::{
     distr::uniform::{SampleRange, SampleUniform},
+    rngs::StdRng,
     *,
 };
 
-pub fn random_range<T, R>(range: R) -> T
+/// All game randomness comes from here, so a level plays out the same way
+/// every time it's started with the same seed and inputs. That only holds as
+/// long as the draws come in the same order, so the simulation's schedule
+/// runs single-threaded; see `replay.rs`. Each app has its own, reseeded by
+/// every [`StartLevel`](crate::level::StartLevel).
+#[derive(Resource)]
+pub struct GameRng(StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(rand::make_rng())
+    }
+}
+
+impl GameRng {
+    pub fn reseed(&mut self, seed: u64) {
+        self.0 = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn range<T, R>(&mut self, range: R) -> T
+    where
+        T: SampleUniform,
+        R: SampleRange<T>,
+    {
+        self.0.random_range(range)
+    }
+
+    pub fn chance(&mut self, p: f32) -> bool {
+        self.0.random_bool(p.into())
+    }
+
+    pub fn vec(&mut self, rmin: f32, rmax: f32) -> Vec2 {
+        polar(&mut self.0, rmin, rmax)
+    }
+}
+
+/// For presentation only, like sound pitch. Doesn't touch the [`GameRng`],
+/// so it's safe to call from anything, at any rate.
+pub fn cosmetic_random_range<T, R>(range: R) -> T
 where
     T: SampleUniform,
     R: SampleRange<T>,
@@ -12,16 +52,39 @@ where
     rand::rng().random_range(range)
 }
 
-pub fn random_chance(p: f32) -> bool {
+/// Like [`GameRng::chance`], for presentation only.
+pub fn cosmetic_random_chance(p: f32) -> bool {
     rand::rng().random_bool(p.into())
 }
 
-pub fn random_vec(rmin: f32, rmax: f32) -> Vec2 {
-    let r = rand::rng().random_range(rmin..rmax);
-    let a = rand::rng().random_range(0.0..std::f32::consts::PI * 2.0);
+/// Like [`GameRng::vec`], for presentation only.
+pub fn cosmetic_random_vec(rmin: f32, rmax: f32) -> Vec2 {
+    polar(&mut rand::rng(), rmin, rmax)
+}
+
+fn polar(rng: &mut impl Rng, rmin: f32, rmax: f32) -> Vec2 {
+    let r = rng.random_range(rmin..rmax);
+    let a = rng.random_range(0.0..std::f32::consts::PI * 2.0);
 
     let x = r * a.cos();
     let y = r * a.sin();
 
     (x, y).into()
 }
+
+/// How far to move towards a target this frame, for exponential smoothing
+/// at `rate` per second that looks the same at any frame rate.
+pub fn smoothing(rate: f32, dt: f32) -> f32 {
+    1.0 - (-rate * dt).exp()
+}
+
+/// Advances a critically damped spring pulling `position` towards `target`,
+/// exactly, so it's stable and frame-rate independent. Higher `omega` is
+/// stiffer.
+pub fn spring_damp(position: &mut Vec3, velocity: &mut Vec3, target: Vec3, omega: f32, dt: f32) {
+    let x = *position - target;
+    let decay = (-omega * dt).exp();
+    let temp = (*velocity + omega * x) * dt;
+    *position = target + (x + temp) * decay;
+    *velocity = (*velocity - omega * temp) * decay;
+}
@@ -0,0 +1,249 @@
+use bevy::color::palettes::tailwind::*;
+use bevy::prelude::*;
+
+use crate::child_rel::Following;
+use crate::ducks::{Done, Duckling, FamilyColor};
+use crate::lake::LAKE_RADIUS;
+use crate::player::PlayerDuck;
+use crate::predators::Predator;
+use crate::weather::StormCell;
+
+pub fn minimap_plugin(app: &mut App) {
+    app.add_systems(Startup, spawn_minimap);
+    app.add_systems(
+        Update,
+        (handle_minimap_keys, update_lpf_minimap_zoom, update_minimap).chain(),
+    );
+
+    app.insert_resource(MinimapZoom {
+        target: 1.0,
+        actual: 1.0,
+    });
+}
+
+/// Width and height of the map, in pixels.
+const MAP_SIZE: f32 = 220.0;
+
+/// World distance from the map's center to its edge at zoom 1.
+const MAP_VIEW_RADIUS: f32 = LAKE_RADIUS * 1.2;
+
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 4.0;
+
+#[derive(Resource)]
+struct MinimapZoom {
+    target: f32,
+    actual: f32,
+}
+
+#[derive(Component)]
+struct Minimap;
+
+#[derive(Component)]
+struct MinimapLake;
+
+/// One of a pool of markers, handed out afresh every frame.
+#[derive(Component)]
+struct MinimapDot;
+
+/// Something to draw on the map this frame.
+struct Blip {
+    pos: Vec2,
+    size: Vec2,
+    color: Color,
+    round: bool,
+    /// Blips in higher layers are drawn on top.
+    layer: i32,
+    /// Clockwise on screen from the +x axis, for markers that show one.
+    heading: f32,
+}
+
+fn spawn_minimap(mut commands: Commands) {
+    commands
+        .spawn((
+            Minimap,
+            Node {
+                position_type: PositionType::Absolute,
+                right: px(16.0),
+                bottom: px(16.0),
+                width: px(MAP_SIZE),
+                height: px(MAP_SIZE),
+                overflow: Overflow::clip(),
+                border: UiRect::all(px(2.0)),
+                border_radius: BorderRadius::all(px(12.0)),
+                ..default()
+            },
+            BackgroundColor(GREEN_900.with_alpha(0.6).into()),
+            BorderColor::all(ZINC_950.with_alpha(0.6)),
+        ))
+        .with_child((
+            MinimapLake,
+            Node {
+                position_type: PositionType::Absolute,
+                border_radius: BorderRadius::MAX,
+                ..default()
+            },
+            BackgroundColor(SKY_700.with_alpha(0.8).into()),
+        ));
+}
+
+fn handle_minimap_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut zoom: ResMut<MinimapZoom>,
+    mut map: Single<&mut Visibility, With<Minimap>>,
+) {
+    if keys.just_pressed(KeyCode::KeyN) {
+        let shown = **map != Visibility::Hidden;
+        **map = if shown {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+
+    if keys.just_pressed(KeyCode::Equal) {
+        zoom.target = (zoom.target * 1.5).min(MAX_ZOOM);
+    }
+    if keys.just_pressed(KeyCode::Minus) {
+        zoom.target = (zoom.target / 1.5).max(MIN_ZOOM);
+    }
+}
+
+fn update_lpf_minimap_zoom(mut zoom: ResMut<MinimapZoom>) {
+    zoom.actual += (zoom.target - zoom.actual) * 0.1;
+}
+
+fn update_minimap(
+    mut commands: Commands,
+    zoom: Res<MinimapZoom>,
+    map: Single<(Entity, &Visibility), With<Minimap>>,
+    mut lake: Single<&mut Node, (With<MinimapLake>, Without<MinimapDot>)>,
+    mut dots: Query<
+        (
+            &mut Node,
+            &mut BackgroundColor,
+            &mut UiTransform,
+            &mut ZIndex,
+            &mut Visibility,
+        ),
+        (With<MinimapDot>, Without<Minimap>),
+    >,
+    player: Single<&Transform, With<PlayerDuck>>,
+    adults: Query<(&Transform, &FamilyColor)>,
+    ducklings: Query<(&Transform, Option<&Following>, Has<Done>), With<Duckling>>,
+    storms: Query<(&Transform, &StormCell)>,
+    predators: Query<&Transform, With<Predator>>,
+) {
+    let (map, map_vis) = *map;
+    if *map_vis == Visibility::Hidden {
+        return;
+    }
+
+    // centered on the player, north up
+    let center = player.translation.xz();
+    let scale = MAP_SIZE / 2.0 / (MAP_VIEW_RADIUS / zoom.actual);
+    let to_map = |p: Vec2| (p - center) * scale + MAP_SIZE / 2.0;
+
+    let lake_size = LAKE_RADIUS * 2.0 * scale;
+    let lake_pos = to_map(Vec2::ZERO) - lake_size / 2.0;
+    lake.left = px(lake_pos.x);
+    lake.top = px(lake_pos.y);
+    lake.width = px(lake_size);
+    lake.height = px(lake_size);
+
+    let mut blips = Vec::new();
+
+    for (tf, cell) in storms {
+        blips.push(Blip {
+            pos: to_map(tf.translation.xz()),
+            size: Vec2::splat(cell.radius * 2.0 * scale),
+            color: SLATE_800.with_alpha(0.5).into(),
+            round: true,
+            layer: 0,
+            heading: 0.0,
+        });
+    }
+
+    for (tf, following, done) in ducklings {
+        let color = match following {
+            _ if done => GREEN_400,
+            Some(_) => AMBER_300,
+            None => RED_500,
+        };
+        blips.push(Blip {
+            pos: to_map(tf.translation.xz()),
+            size: Vec2::splat(5.0),
+            color: color.into(),
+            round: true,
+            layer: 1,
+            heading: 0.0,
+        });
+    }
+
+    for tf in predators {
+        blips.push(Blip {
+            pos: to_map(tf.translation.xz()),
+            size: Vec2::splat(7.0),
+            color: ROSE_600.into(),
+            round: false,
+            layer: 1,
+            heading: std::f32::consts::FRAC_PI_4,
+        });
+    }
+
+    for (tf, family) in adults {
+        blips.push(Blip {
+            pos: to_map(tf.translation.xz()),
+            size: Vec2::splat(10.0),
+            color: family.0,
+            round: true,
+            layer: 2,
+            heading: 0.0,
+        });
+    }
+
+    let forward = player.forward();
+    blips.push(Blip {
+        pos: to_map(center),
+        size: Vec2::new(12.0, 6.0),
+        color: Color::WHITE,
+        round: false,
+        layer: 3,
+        heading: forward.z.atan2(forward.x),
+    });
+
+    // grow the pool; the new dots are used from next frame
+    let n_dots = dots.iter().count();
+    for _ in n_dots..blips.len() {
+        commands.spawn((
+            MinimapDot,
+            Node {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            ChildOf(map),
+        ));
+    }
+
+    let mut blips = blips.into_iter();
+    for (mut node, mut bg, mut ui_tf, mut z, mut vis) in &mut dots {
+        let Some(blip) = blips.next() else {
+            vis.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        vis.set_if_neq(Visibility::Inherited);
+
+        node.left = px(blip.pos.x - blip.size.x / 2.0);
+        node.top = px(blip.pos.y - blip.size.y / 2.0);
+        node.width = px(blip.size.x);
+        node.height = px(blip.size.y);
+        node.border_radius = if blip.round {
+            BorderRadius::MAX
+        } else {
+            BorderRadius::ZERO
+        };
+        bg.0 = blip.color;
+        ui_tf.rotation = Rot2::radians(blip.heading);
+        z.set_if_neq(ZIndex(blip.layer));
+    }
+}
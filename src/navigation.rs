@@ -0,0 +1,682 @@
+use bevy::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::child_rel::DuckParent;
+use crate::ducks::{Boid, Duck, TargetPosition};
+use crate::lake::LAKE_RADIUS;
+
+pub fn navigation_plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (
+            update_nav_grid,
+            plan_paths,
+            advance_waypoints,
+            avoid_obstacles,
+        )
+            .chain()
+            .in_set(NavigationSystems),
+    );
+
+    app.insert_resource(NavGrid::lake());
+}
+
+/// Runs before anything that steers along a [`NavPath`].
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NavigationSystems;
+
+/// Metres per side of a grid cell.
+const CELL_SIZE: f32 = 4.0;
+
+/// Water kept between a planned path and an obstacle.
+const CLEARANCE: f32 = 1.0;
+
+/// A waypoint counts as reached this close.
+const WAYPOINT_RADIUS: f32 = 2.5;
+
+/// Ducks start steering around obstacles this far from their edge.
+const AVOID_DISTANCE: f32 = 3.0;
+
+/// Path costs, in tenths of a cell.
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// Extra cost of swimming through a cell another family is in. Enough to go a
+/// little way round, not so much that a crowded gap is never taken.
+const CROWDED_COST: u32 = 40;
+
+/// Something ducks have to swim around.
+#[derive(Component, Debug)]
+pub struct Obstacle {
+    pub radius: f32,
+}
+
+/// Which cells of the lake can be swum through.
+#[derive(Resource, Debug, Clone)]
+pub struct NavGrid {
+    size: IVec2,
+    origin: Vec2,
+    cell: f32,
+    /// Blocked no matter what's floating about, like the shore.
+    fixed: Vec<bool>,
+    blocked: Vec<bool>,
+    /// The family with ducks in each cell, or a placeholder where families
+    /// mix. Ducks would rather go round other families than through them, but
+    /// swim along with their own.
+    crowded: Vec<Option<Entity>>,
+    /// Cells that were blocked or freed by the last update, so cached paths
+    /// going past them know to replan. Ducks coming and going don't count, or
+    /// everyone would replan every step.
+    changed: Vec<IVec2>,
+}
+
+impl NavGrid {
+    /// An open grid of `size` cells with its corner at `origin`.
+    pub fn new(size: IVec2, cell: f32, origin: Vec2) -> Self {
+        let n = (size.x * size.y) as usize;
+        Self {
+            size,
+            origin,
+            cell,
+            fixed: vec![false; n],
+            blocked: vec![false; n],
+            crowded: vec![None; n],
+            changed: Vec::new(),
+        }
+    }
+
+    /// The lake, with everything past the shore blocked.
+    fn lake() -> Self {
+        let cells = (LAKE_RADIUS * 2.0 / CELL_SIZE).ceil() as i32;
+        let mut grid = Self::new(IVec2::splat(cells), CELL_SIZE, Vec2::splat(-LAKE_RADIUS));
+        for y in 0..cells {
+            for x in 0..cells {
+                let c = IVec2::new(x, y);
+                if grid.centre_of(c).xz().length() > LAKE_RADIUS {
+                    grid.set_fixed(c);
+                }
+            }
+        }
+        grid
+    }
+
+    fn index(&self, c: IVec2) -> Option<usize> {
+        let inside = c.x >= 0 && c.y >= 0 && c.x < self.size.x && c.y < self.size.y;
+        inside.then(|| (c.y * self.size.x + c.x) as usize)
+    }
+
+    fn cell_at(&self, i: usize) -> IVec2 {
+        IVec2::new(i as i32 % self.size.x, i as i32 / self.size.x)
+    }
+
+    pub fn cell_of(&self, p: Vec3) -> IVec2 {
+        ((p.xz() - self.origin) / self.cell).floor().as_ivec2()
+    }
+
+    pub fn centre_of(&self, c: IVec2) -> Vec3 {
+        let p = self.origin + (c.as_vec2() + 0.5) * self.cell;
+        Vec3::new(p.x, 0.0, p.y)
+    }
+
+    /// Off the grid counts as blocked.
+    pub fn is_blocked(&self, c: IVec2) -> bool {
+        self.index(c).is_none_or(|i| self.blocked[i])
+    }
+
+    /// Whether ducks other than `family`'s are in a cell.
+    fn is_crowded(&self, c: IVec2, family: Option<Entity>) -> bool {
+        self.index(c)
+            .and_then(|i| self.crowded[i])
+            .is_some_and(|f| Some(f) != family)
+    }
+
+    /// Blocks a cell for good.
+    pub fn set_fixed(&mut self, c: IVec2) {
+        if let Some(i) = self.index(c) {
+            self.fixed[i] = true;
+            self.blocked[i] = true;
+        }
+    }
+
+    /// Cells blocked by obstacles rather than the grid itself.
+    pub fn obstacle_cells(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (0..self.blocked.len())
+            .filter(|i| self.blocked[*i] && !self.fixed[*i])
+            .map(|i| self.cell_at(i))
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell
+    }
+
+    /// The cells a straight line between two cells passes over.
+    fn cells_between(a: IVec2, b: IVec2) -> impl Iterator<Item = IVec2> {
+        let (a, b) = (a.as_vec2(), b.as_vec2());
+        let steps = (a.distance(b) * 4.0).ceil().max(1.0) as i32;
+        (0..=steps).map(move |i| a.lerp(b, i as f32 / steps as f32).round().as_ivec2())
+    }
+
+    /// Whether a duck of `family` could swim straight between two cells
+    /// without going through anyone else. It may still set off from or make
+    /// for a crowded cell, since one is usually its own and the other the duck
+    /// it's after.
+    pub fn line_of_sight(&self, a: IVec2, b: IVec2, family: Option<Entity>) -> bool {
+        let mut prev = a;
+        Self::cells_between(a, b).all(|c| {
+            // a diagonal step past a blocked cell cuts its corner, which the
+            // search doesn't allow either
+            let squeezed = c.x != prev.x
+                && c.y != prev.y
+                && (self.is_blocked(IVec2::new(c.x, prev.y))
+                    || self.is_blocked(IVec2::new(prev.x, c.y)));
+            prev = c;
+            let open = !self.is_blocked(c) && (c == a || c == b || !self.is_crowded(c, family));
+            !squeezed && open
+        })
+    }
+
+    /// Whether a route through `cells` passes over, or right beside, a cell
+    /// that changed in the last update. A cell blocked on the way means the
+    /// route is cut; one freed beside it may mean a shorter way round.
+    fn route_changed(&self, cells: &[IVec2]) -> bool {
+        !self.changed.is_empty()
+            && cells
+                .windows(2)
+                .flat_map(|leg| Self::cells_between(leg[0], leg[1]))
+                .any(|c| {
+                    self.changed
+                        .iter()
+                        .any(|d| (*d - c).abs().max_element() <= 1)
+                })
+    }
+
+    /// A* from `from` to `to` for a duck of `family`, smoothed down to the
+    /// cells where the path turns. Excludes `from`, which may itself be
+    /// blocked.
+    pub fn find_path(
+        &self,
+        from: IVec2,
+        to: IVec2,
+        family: Option<Entity>,
+        scratch: &mut PathScratch,
+    ) -> Option<Vec<IVec2>> {
+        let goal = self.index(to)?;
+        let start = self.index(from)?;
+        if self.blocked[goal] {
+            return None;
+        }
+        if start == goal {
+            return Some(vec![to]);
+        }
+
+        let n = self.blocked.len();
+        let PathScratch {
+            cost,
+            came_from,
+            open,
+        } = scratch;
+        cost.clear();
+        cost.resize(n, u32::MAX);
+        came_from.clear();
+        came_from.resize(n, usize::MAX);
+        open.clear();
+
+        let heuristic = |c: IVec2| {
+            let d = (c - to).abs();
+            let (lo, hi) = (d.x.min(d.y) as u32, d.x.max(d.y) as u32);
+            DIAGONAL_COST * lo + STRAIGHT_COST * (hi - lo)
+        };
+
+        cost[start] = 0;
+        open.push(Reverse((heuristic(from), start)));
+
+        while let Some(Reverse((_, i))) = open.pop() {
+            if i == goal {
+                break;
+            }
+            let c = self.cell_at(i);
+
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if dx == 0 && dy == 0 {
+                        continue;
+                    }
+                    let next = c + IVec2::new(dx, dy);
+                    if self.is_blocked(next) {
+                        continue;
+                    }
+                    let diagonal = dx != 0 && dy != 0;
+                    // don't cut corners
+                    if diagonal
+                        && (self.is_blocked(c + IVec2::new(dx, 0))
+                            || self.is_blocked(c + IVec2::new(0, dy)))
+                    {
+                        continue;
+                    }
+
+                    let Some(j) = self.index(next) else {
+                        continue;
+                    };
+                    let mut step = if diagonal {
+                        DIAGONAL_COST
+                    } else {
+                        STRAIGHT_COST
+                    };
+                    if next != to && self.is_crowded(next, family) {
+                        step += CROWDED_COST;
+                    }
+                    let new_cost = cost[i] + step;
+                    if new_cost < cost[j] {
+                        cost[j] = new_cost;
+                        came_from[j] = i;
+                        open.push(Reverse((new_cost + heuristic(next), j)));
+                    }
+                }
+            }
+        }
+
+        if cost[goal] == u32::MAX {
+            return None;
+        }
+
+        let mut cells = vec![to];
+        let mut i = goal;
+        while came_from[i] != start {
+            i = came_from[i];
+            cells.push(self.cell_at(i));
+        }
+        cells.reverse();
+
+        // keep only the corners
+        let mut smoothed = Vec::new();
+        let mut anchor = from;
+        let mut k = 0;
+        while k < cells.len() {
+            let mut furthest = k;
+            while furthest + 1 < cells.len()
+                && self.line_of_sight(anchor, cells[furthest + 1], family)
+            {
+                furthest += 1;
+            }
+            anchor = cells[furthest];
+            smoothed.push(anchor);
+            k = furthest + 1;
+        }
+        Some(smoothed)
+    }
+}
+
+/// Buffers [`NavGrid::find_path`] works in, kept between searches so each
+/// one doesn't allocate a grid's worth of them.
+#[derive(Debug, Default)]
+pub struct PathScratch {
+    cost: Vec<u32>,
+    came_from: Vec<usize>,
+    open: BinaryHeap<Reverse<(u32, usize)>>,
+}
+
+/// The route a duck is taking to its [`TargetPosition`]. Empty when it can
+/// swim straight there.
+#[derive(Component, Debug, Default)]
+pub struct NavPath {
+    goal: Option<IVec2>,
+    waypoints: Vec<Vec3>,
+    next: usize,
+}
+
+impl NavPath {
+    /// Where to head for now, if not straight for the target.
+    pub fn waypoint(&self) -> Option<Vec3> {
+        self.waypoints.get(self.next).copied()
+    }
+
+    /// The waypoints still to come.
+    pub fn remaining(&self) -> &[Vec3] {
+        self.waypoints.get(self.next..).unwrap_or_default()
+    }
+}
+
+fn update_nav_grid(
+    mut grid: ResMut<NavGrid>,
+    obstacles: Query<(&Transform, &Obstacle)>,
+    ducks: Query<(Entity, &Transform, Option<&DuckParent>), With<Duck>>,
+) {
+    let mut blocked = grid.fixed.clone();
+
+    for (tf, obstacle) in &obstacles {
+        let reach = obstacle.radius + CLEARANCE + grid.cell * 0.5;
+        let lo = grid.cell_of(tf.translation - Vec3::splat(reach));
+        let hi = grid.cell_of(tf.translation + Vec3::splat(reach));
+        for y in lo.y..=hi.y {
+            for x in lo.x..=hi.x {
+                let c = IVec2::new(x, y);
+                if let Some(i) = grid.index(c)
+                    && grid.centre_of(c).distance(tf.translation.with_y(0.0)) < reach
+                {
+                    blocked[i] = true;
+                }
+            }
+        }
+    }
+
+    let mut crowded = vec![None; blocked.len()];
+    for (duck, tf, parent) in &ducks {
+        if let Some(i) = grid.index(grid.cell_of(tf.translation)) {
+            let family = family_of(duck, parent);
+            crowded[i] = match crowded[i] {
+                Some(f) if f != family => Some(Entity::PLACEHOLDER),
+                _ => Some(family),
+            };
+        }
+    }
+
+    grid.changed = (0..blocked.len())
+        .filter(|i| blocked[*i] != grid.blocked[*i])
+        .map(|i| grid.cell_at(i))
+        .collect();
+    grid.blocked = blocked;
+    grid.crowded = crowded;
+}
+
+/// A duckling's family is its parent's; anyone else heads their own.
+fn family_of(duck: Entity, parent: Option<&DuckParent>) -> Entity {
+    parent.map_or(duck, |p| p.0)
+}
+
+/// Replans only when the target moves to another cell, or the water changes
+/// somewhere along the way.
+fn plan_paths(
+    grid: Res<NavGrid>,
+    ducks: Query<(
+        Entity,
+        &Transform,
+        &TargetPosition,
+        &mut NavPath,
+        Option<&DuckParent>,
+    )>,
+    mut scratch: Local<PathScratch>,
+) {
+    for (duck, tf, target, mut path, parent) in ducks {
+        let family = Some(family_of(duck, parent));
+        let from = grid.cell_of(tf.translation);
+        let goal = grid.cell_of(target.pos);
+        if path.goal == Some(goal) {
+            let route: Vec<IVec2> = std::iter::once(from)
+                .chain(path.remaining().iter().map(|w| grid.cell_of(*w)))
+                .chain(std::iter::once(goal))
+                .collect();
+            if !grid.route_changed(&route) {
+                continue;
+            }
+        }
+
+        path.goal = Some(goal);
+        path.next = 0;
+        path.waypoints.clear();
+
+        if grid.line_of_sight(from, goal, family) {
+            continue;
+        }
+        // if there's no way through, just make for it and hope
+        if let Some(cells) = grid.find_path(from, goal, family, &mut scratch) {
+            // the last cell is the target's own, which is steered to directly
+            let corners = &cells[..cells.len().saturating_sub(1)];
+            path.waypoints = corners.iter().map(|c| grid.centre_of(*c)).collect();
+        }
+    }
+}
+
+fn advance_waypoints(ducks: Query<(&Transform, &mut NavPath)>) {
+    for (tf, mut path) in ducks {
+        if let Some(w) = path.waypoint()
+            && w.distance(tf.translation.with_y(0.0)) < WAYPOINT_RADIUS
+        {
+            path.next += 1;
+        }
+    }
+}
+
+/// Nudges ducks round obstacles right in front of them, which the grid is
+/// too coarse to catch.
+fn avoid_obstacles(
+    boids: Query<(&mut Boid, &Transform)>,
+    obstacles: Query<(&Transform, &Obstacle)>,
+) {
+    for (mut boid, tf) in boids {
+        boid.avoid = Vec3::ZERO;
+        for (obstacle_tf, obstacle) in &obstacles {
+            let delta = (tf.translation - obstacle_tf.translation).with_y(0.0);
+            let gap = delta.length() - obstacle.radius;
+            if gap < AVOID_DISTANCE {
+                boid.avoid += delta.normalize_or_zero() * (AVOID_DISTANCE - gap) * 2.0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> NavGrid {
+        NavGrid::new(IVec2::splat(10), 1.0, Vec2::ZERO)
+    }
+
+    /// Blocks column `x`, except for the cells in `gap`.
+    fn wall(grid: &mut NavGrid, x: i32, gap: std::ops::Range<i32>) {
+        for y in 0..10 {
+            if !gap.contains(&y) {
+                grid.set_fixed(IVec2::new(x, y));
+            }
+        }
+    }
+
+    /// A path for a duck with nobody else about.
+    fn find_path(grid: &NavGrid, from: IVec2, to: IVec2) -> Option<Vec<IVec2>> {
+        grid.find_path(from, to, None, &mut PathScratch::default())
+    }
+
+    /// Every leg of the path, starting from `from`, can be swum straight.
+    fn is_swimmable(grid: &NavGrid, from: IVec2, path: &[IVec2]) -> bool {
+        std::iter::once(from)
+            .chain(path.iter().copied())
+            .collect::<Vec<_>>()
+            .windows(2)
+            .all(|leg| grid.line_of_sight(leg[0], leg[1], None))
+    }
+
+    #[test]
+    fn open_water_is_one_straight_swim() {
+        let path = find_path(&grid(), IVec2::new(0, 0), IVec2::new(9, 6));
+        assert_eq!(path, Some(vec![IVec2::new(9, 6)]));
+    }
+
+    #[test]
+    fn finds_the_gap_in_a_wall() {
+        let mut grid = grid();
+        wall(&mut grid, 5, 8..9);
+        let (from, to) = (IVec2::new(1, 1), IVec2::new(9, 1));
+
+        let path = find_path(&grid, from, to).unwrap();
+        assert_eq!(path.last(), Some(&to));
+        assert!(is_swimmable(&grid, from, &path));
+        assert!(path.iter().any(|c| c.y >= 7), "{:?}", path);
+    }
+
+    #[test]
+    fn blocked_or_walled_off_goals_are_unreachable() {
+        let mut grid = grid();
+        grid.set_fixed(IVec2::new(9, 9));
+        assert_eq!(find_path(&grid, IVec2::ZERO, IVec2::new(9, 9)), None);
+
+        wall(&mut grid, 5, 0..0);
+        assert_eq!(find_path(&grid, IVec2::ZERO, IVec2::new(9, 0)), None);
+    }
+
+    #[test]
+    fn never_cuts_a_corner() {
+        let mut grid = grid();
+        grid.set_fixed(IVec2::new(1, 0));
+        grid.set_fixed(IVec2::new(0, 1));
+        assert_eq!(find_path(&grid, IVec2::ZERO, IVec2::ONE), None);
+
+        // with one side open it goes round that way
+        let mut grid = self::grid();
+        grid.set_fixed(IVec2::new(1, 0));
+        let path = find_path(&grid, IVec2::ZERO, IVec2::ONE).unwrap();
+        assert_eq!(path, vec![IVec2::new(0, 1), IVec2::ONE]);
+    }
+
+    #[test]
+    fn smooths_down_to_the_corners() {
+        let mut grid = grid();
+        wall(&mut grid, 5, 8..10);
+        let (from, to) = (IVec2::new(2, 2), IVec2::new(8, 2));
+
+        let path = find_path(&grid, from, to).unwrap();
+        assert!(is_swimmable(&grid, from, &path));
+        // dropping any waypoint would run into the wall
+        let with_start: Vec<IVec2> = std::iter::once(from).chain(path.iter().copied()).collect();
+        for turn in with_start.windows(3) {
+            assert!(!grid.line_of_sight(turn[0], turn[2], None), "{:?}", path);
+        }
+        assert!(path.len() <= 3, "{:?}", path);
+    }
+
+    #[test]
+    fn only_paths_past_changed_water_replan() {
+        let mut app = App::new();
+        app.add_systems(Update, (update_nav_grid, plan_paths).chain());
+        app.insert_resource(NavGrid::new(IVec2::splat(20), 1.0, Vec2::ZERO));
+
+        let mut duck = |from: Vec3, to: Vec3| {
+            app.world_mut()
+                .spawn((
+                    Transform::from_translation(from),
+                    TargetPosition { pos: to },
+                    NavPath::default(),
+                ))
+                .id()
+        };
+        let crossing = duck(Vec3::new(2.5, 0.0, 10.5), Vec3::new(17.5, 0.0, 10.5));
+        let elsewhere = duck(Vec3::new(2.5, 0.0, 1.5), Vec3::new(17.5, 0.0, 1.5));
+        app.update();
+
+        // mark both paths, to tell whether they get planned again
+        for e in [crossing, elsewhere] {
+            app.world_mut().get_mut::<NavPath>(e).unwrap().next = 7;
+        }
+        app.world_mut().spawn((
+            Transform::from_xyz(10.0, 0.0, 10.0),
+            Obstacle { radius: 1.0 },
+        ));
+        app.update();
+
+        let path = |e| app.world().get::<NavPath>(e).unwrap();
+        assert_eq!(path(crossing).next, 0);
+        assert!(!path(crossing).waypoints.is_empty());
+        assert_eq!(path(elsewhere).next, 7);
+    }
+
+    #[test]
+    fn paths_go_round_other_families() {
+        let mut app = App::new();
+        app.add_systems(Update, (update_nav_grid, plan_paths).chain());
+        app.insert_resource(NavGrid::new(IVec2::splat(10), 1.0, Vec2::ZERO));
+
+        // one in the way, and one at the end of the path
+        let in_the_way = Vec3::new(5.5, 0.0, 5.5);
+        let (from, to) = (Vec3::new(1.5, 0.0, 5.5), Vec3::new(8.5, 0.0, 5.5));
+        let other = app
+            .world_mut()
+            .spawn((Duck::default(), Transform::from_translation(in_the_way)))
+            .id();
+        app.world_mut()
+            .spawn((Duck::default(), Transform::from_translation(to)));
+        let swimmer = app
+            .world_mut()
+            .spawn((
+                Duck::default(),
+                Transform::from_translation(from),
+                TargetPosition { pos: to },
+                NavPath::default(),
+            ))
+            .id();
+        app.update();
+
+        let grid = app.world().resource::<NavGrid>();
+        let crowded = grid.cell_of(in_the_way);
+        assert!(!grid.is_blocked(crowded));
+
+        let path = app.world().get::<NavPath>(swimmer).unwrap();
+        assert!(!path.waypoints.is_empty(), "swam straight through");
+        let cells: Vec<IVec2> = std::iter::once(grid.cell_of(from))
+            .chain(path.waypoints.iter().map(|w| grid.cell_of(*w)))
+            .chain(std::iter::once(grid.cell_of(to)))
+            .collect();
+        assert!(!cells.contains(&crowded), "{:?}", cells);
+        for leg in cells.windows(2) {
+            assert!(
+                grid.line_of_sight(leg[0], leg[1], Some(swimmer)),
+                "{:?}",
+                cells
+            );
+        }
+
+        // ducks moving about don't set everyone replanning
+        app.world_mut().get_mut::<NavPath>(swimmer).unwrap().next = 7;
+        app.world_mut()
+            .get_mut::<Transform>(other)
+            .unwrap()
+            .translation
+            .z = 1.5;
+        app.update();
+        assert_eq!(app.world().get::<NavPath>(swimmer).unwrap().next, 7);
+    }
+
+    #[test]
+    fn ducklings_swim_through_their_own_family() {
+        let mut app = App::new();
+        app.add_systems(Update, (update_nav_grid, plan_paths).chain());
+        app.insert_resource(NavGrid::new(IVec2::splat(10), 1.0, Vec2::ZERO));
+
+        let parent = app
+            .world_mut()
+            .spawn((Duck::default(), Transform::from_xyz(8.5, 0.0, 5.5)))
+            .id();
+        app.world_mut().spawn((
+            Duck::default(),
+            DuckParent(parent),
+            Transform::from_xyz(5.5, 0.0, 5.5),
+        ));
+        let duckling = app
+            .world_mut()
+            .spawn((
+                Duck::default(),
+                DuckParent(parent),
+                Transform::from_xyz(1.5, 0.0, 5.5),
+                TargetPosition {
+                    pos: Vec3::new(8.5, 0.0, 5.5),
+                },
+                NavPath::default(),
+            ))
+            .id();
+        app.update();
+
+        let path = app.world().get::<NavPath>(duckling).unwrap();
+        assert!(path.waypoints.is_empty(), "{:?}", path.waypoints);
+    }
+
+    #[test]
+    fn squeezes_past_a_duck_rather_than_giving_up() {
+        let mut grid = grid();
+        wall(&mut grid, 5, 4..5);
+        let gap = IVec2::new(5, 4);
+        let i = grid.index(gap).unwrap();
+        grid.crowded[i] = Some(Entity::PLACEHOLDER);
+
+        let path = find_path(&grid, IVec2::new(1, 4), IVec2::new(9, 4)).unwrap();
+        assert!(path.contains(&gap), "{:?}", path);
+    }
+}
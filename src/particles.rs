@@ -97,7 +97,7 @@ fn spawn_ripples_at_sources(
     mut messages: MessageReader<Splash>,
 ) {
     for msg in messages.read() {
-        let size = random_range(0.05..=0.25);
+        let size = cosmetic_random_range(0.05..=0.25);
 
         let tf = Transform::from_translation(msg.position).with_scale(Vec3::splat(size));
         commands.spawn((
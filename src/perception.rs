@@ -0,0 +1,338 @@
+use bevy::prelude::*;
+
+use crate::atmosphere::SightRange;
+use crate::ducks::Duck;
+use crate::particles::Splash;
+use crate::predators::Predator;
+use crate::quacks::QuackKind;
+use crate::text_bubble::Quack;
+use crate::weather::Lightning;
+
+pub fn perception_plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (
+            forget_old_percepts,
+            see,
+            hear_quacks,
+            hear_splashes,
+            hear_thunder,
+        )
+            .chain()
+            .in_set(PerceptionSystems),
+    );
+}
+
+/// Runs before anything that asks a duck what it knows.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PerceptionSystems;
+
+/// Seconds before something no longer seen or heard is forgotten.
+const MEMORY_TIME: f32 = 6.0;
+
+/// Anything this close is noticed whichever way the duck is facing.
+const TOUCH_RADIUS: f32 = 1.5;
+
+/// Sourceless sounds this close together are remembered as one.
+const NOISE_MERGE_RADIUS: f32 = 3.0;
+
+/// Most sourceless sounds a duck keeps track of at once.
+const MAX_NOISES: usize = 8;
+
+/// Thunder carries right across the lake.
+const THUNDER_RADIUS: f32 = 500.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    Quack(QuackKind),
+    Splash,
+    Thunder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sense {
+    Sight,
+    Hearing(Sound),
+}
+
+/// Something a duck noticed, and how long ago.
+#[derive(Debug, Clone)]
+pub struct Percept {
+    /// The duck or predator that was noticed; `None` for splashes and thunder.
+    pub source: Option<Entity>,
+    pub position: Vec3,
+    pub sense: Sense,
+    pub age: f32,
+}
+
+/// What a duck can see and hear, and what it remembers of it.
+#[derive(Component, Debug)]
+pub struct Perception {
+    /// Cosine of the half-angle of the view cone.
+    view_cos: f32,
+    /// How far the duck sees on a clear day. The sky can only shorten it.
+    sight: f32,
+    hearing: f32,
+    memory: Vec<Percept>,
+}
+
+impl Perception {
+    pub fn adult() -> Self {
+        Self::new(140.0, 40.0, 25.0)
+    }
+
+    pub fn duckling() -> Self {
+        Self::new(120.0, 20.0, 30.0)
+    }
+
+    fn new(view_angle_degrees: f32, sight: f32, hearing: f32) -> Self {
+        Self {
+            view_cos: (view_angle_degrees / 2.0).to_radians().cos(),
+            sight,
+            hearing,
+            memory: Vec::new(),
+        }
+    }
+
+    /// Ducklings see and hear further as they grow.
+    pub fn set_ranges(&mut self, sight: f32, hearing: f32) {
+        self.sight = sight;
+        self.hearing = hearing;
+    }
+
+    /// Everything still remembered.
+    pub fn percepts(&self) -> impl Iterator<Item = &Percept> {
+        self.memory.iter()
+    }
+
+    /// The last place `entity` was seen or heard, if it's still remembered.
+    pub fn recall(&self, entity: Entity) -> Option<&Percept> {
+        self.memory
+            .iter()
+            .filter(|p| p.source == Some(entity))
+            .min_by(|a, b| a.age.total_cmp(&b.age))
+    }
+
+    pub fn remembers(&self, entity: Entity) -> bool {
+        self.recall(entity).is_some()
+    }
+
+    /// The most recent sound of the given kind.
+    pub fn last_heard(&self, sound: Sound) -> Option<&Percept> {
+        self.memory
+            .iter()
+            .filter(|p| p.sense == Sense::Hearing(sound))
+            .min_by(|a, b| a.age.total_cmp(&b.age))
+    }
+
+    fn remember(&mut self, source: Option<Entity>, position: Vec3, sense: Sense) {
+        let existing = self.memory.iter_mut().find(|p| {
+            p.sense == sense
+                && match source {
+                    Some(_) => p.source == source,
+                    None => {
+                        p.source.is_none() && p.position.distance(position) < NOISE_MERGE_RADIUS
+                    }
+                }
+        });
+
+        match existing {
+            Some(p) => {
+                p.position = position;
+                p.age = 0.0;
+            }
+            None => {
+                self.memory.push(Percept {
+                    source,
+                    position,
+                    sense,
+                    age: 0.0,
+                });
+            }
+        }
+
+        if source.is_none() {
+            let noises = self.memory.iter().filter(|p| p.source.is_none()).count();
+            if noises > MAX_NOISES
+                && let Some(i) = self
+                    .memory
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| p.source.is_none())
+                    .max_by(|a, b| a.1.age.total_cmp(&b.1.age))
+                    .map(|(i, _)| i)
+            {
+                self.memory.swap_remove(i);
+            }
+        }
+    }
+
+    /// Ages everything remembered by `dt` seconds, forgetting what's too old.
+    fn forget(&mut self, dt: f32) {
+        self.memory.retain_mut(|p| {
+            p.age += dt;
+            p.age < MEMORY_TIME
+        });
+    }
+
+    fn can_see(&self, from: &Transform, to: Vec3, range: f32) -> bool {
+        let delta = to - from.translation;
+        let distance = delta.length();
+        if distance < TOUCH_RADIUS {
+            return true;
+        }
+        // ducks swim along their local +Z
+        distance < range && from.local_z().dot(delta / distance) > self.view_cos
+    }
+}
+
+fn forget_old_percepts(time: Res<Time<Fixed>>, perceivers: Query<&mut Perception>) {
+    let dt = time.delta_secs();
+    for mut perception in perceivers {
+        perception.forget(dt);
+    }
+}
+
+fn see(
+    sky: Res<SightRange>,
+    perceivers: Query<(Entity, &Transform, &mut Perception)>,
+    visible: Query<(Entity, &Transform), Or<(With<Duck>, With<Predator>)>>,
+) {
+    for (me, tf, mut perception) in perceivers {
+        let range = perception.sight.min(**sky);
+        for (other, other_tf) in &visible {
+            if other != me && perception.can_see(tf, other_tf.translation, range) {
+                perception.remember(Some(other), other_tf.translation, Sense::Sight);
+            }
+        }
+    }
+}
+
+fn hear_quacks(
+    mut quacks: MessageReader<Quack>,
+    perceivers: Query<(Entity, &Transform, &mut Perception)>,
+    transforms: Query<&Transform, With<Duck>>,
+) {
+    let quacks: Vec<_> = quacks
+        .read()
+        .filter_map(|q| Some((q.entity, q.kind, transforms.get(q.entity).ok()?.translation)))
+        .collect();
+
+    for (me, tf, mut perception) in perceivers {
+        for (duck, kind, position) in &quacks {
+            if *duck != me && tf.translation.distance(*position) < perception.hearing {
+                let sense = Sense::Hearing(Sound::Quack(*kind));
+                perception.remember(Some(*duck), *position, sense);
+            }
+        }
+    }
+}
+
+fn hear_splashes(
+    mut splashes: MessageReader<Splash>,
+    perceivers: Query<(&Transform, &mut Perception)>,
+) {
+    let splashes: Vec<_> = splashes.read().map(|s| s.position).collect();
+
+    for (tf, mut perception) in perceivers {
+        for position in &splashes {
+            if tf.translation.distance(*position) < perception.hearing {
+                perception.remember(None, *position, Sense::Hearing(Sound::Splash));
+            }
+        }
+    }
+}
+
+fn hear_thunder(
+    strikes: Query<&Transform, Added<Lightning>>,
+    mut perceivers: Query<(&Transform, &mut Perception)>,
+) {
+    for strike in &strikes {
+        let position = strike.translation.with_y(0.0);
+        for (tf, mut perception) in &mut perceivers {
+            if tf.translation.distance(position) < THUNDER_RADIUS {
+                perception.remember(None, position, Sense::Hearing(Sound::Thunder));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where a duck at the origin, swimming along +Z, sees something
+    /// `degrees` off to its side at `distance`.
+    fn off_to_the_side(degrees: f32, distance: f32) -> Vec3 {
+        let a = degrees.to_radians();
+        Vec3::new(a.sin(), 0.0, a.cos()) * distance
+    }
+
+    #[test]
+    fn sees_within_half_the_view_angle_either_side() {
+        let duck = Perception::adult();
+        let at = Transform::default();
+        assert!(duck.can_see(&at, off_to_the_side(0.0, 10.0), 40.0));
+        assert!(duck.can_see(&at, off_to_the_side(65.0, 10.0), 40.0));
+        assert!(duck.can_see(&at, off_to_the_side(-65.0, 10.0), 40.0));
+        assert!(!duck.can_see(&at, off_to_the_side(75.0, 10.0), 40.0));
+        assert!(!duck.can_see(&at, off_to_the_side(180.0, 10.0), 40.0));
+        assert!(!duck.can_see(&at, off_to_the_side(0.0, 50.0), 40.0));
+    }
+
+    #[test]
+    fn feels_what_it_cannot_see() {
+        let duck = Perception::adult();
+        let at = Transform::default();
+        assert!(duck.can_see(&at, off_to_the_side(180.0, 1.0), 40.0));
+    }
+
+    #[test]
+    fn forgets_after_a_while() {
+        let mut duck = Perception::duckling();
+        let other = Entity::from_raw_u32(1).unwrap();
+        duck.remember(Some(other), Vec3::ZERO, Sense::Sight);
+        duck.forget(MEMORY_TIME - 1.0);
+        assert!(duck.remembers(other));
+
+        // seeing it again starts over
+        duck.remember(Some(other), Vec3::X, Sense::Sight);
+        duck.forget(MEMORY_TIME - 1.0);
+        assert_eq!(duck.recall(other).unwrap().position, Vec3::X);
+
+        duck.forget(1.0);
+        assert!(!duck.remembers(other));
+    }
+
+    #[test]
+    fn nearby_noises_merge() {
+        let mut duck = Perception::adult();
+        let splash = Sense::Hearing(Sound::Splash);
+        duck.remember(None, Vec3::ZERO, splash);
+        duck.remember(None, Vec3::X * 2.0, splash);
+        assert_eq!(duck.percepts().count(), 1);
+        assert_eq!(
+            duck.last_heard(Sound::Splash).unwrap().position,
+            Vec3::X * 2.0
+        );
+
+        duck.remember(None, Vec3::X * 10.0, splash);
+        duck.remember(None, Vec3::X * 10.0, Sense::Hearing(Sound::Thunder));
+        assert_eq!(duck.percepts().count(), 3);
+    }
+
+    #[test]
+    fn keeps_only_the_latest_noises() {
+        let mut duck = Perception::adult();
+        for i in 0..MAX_NOISES + 2 {
+            duck.remember(
+                None,
+                Vec3::X * 10.0 * i as f32,
+                Sense::Hearing(Sound::Splash),
+            );
+            duck.forget(0.1);
+        }
+        assert_eq!(duck.percepts().count(), MAX_NOISES);
+        assert!(duck.percepts().all(|p| p.position.x >= 20.0));
+    }
+}
@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use serde::{Serialize, de::DeserializeOwned};
+use std::path::PathBuf;
+
+/// Directory, relative to the working directory, where settings and other
+/// player data are kept.
+const DATA_DIR: &str = "userdata";
+
+/// Where settings and other player data are read from and written to.
+/// Plugins that keep data add the default; tests point it somewhere
+/// disposable instead.
+#[derive(Resource, Debug, Clone)]
+pub struct DataDir(pub PathBuf);
+
+impl Default for DataDir {
+    fn default() -> Self {
+        Self(PathBuf::from(DATA_DIR))
+    }
+}
+
+impl DataDir {
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+
+    pub fn save_ron<T: Serialize>(&self, name: &str, value: &T) -> Result {
+        let path = self.path(name);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let s = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?;
+        std::fs::write(&path, s)?;
+        info!("Saved {}", path.display());
+        Ok(())
+    }
+
+    pub fn load_ron<T: DeserializeOwned>(&self, name: &str) -> Result<T> {
+        let path = self.path(name);
+        let s = std::fs::read_to_string(&path)?;
+        let value = ron::from_str(&s)?;
+        info!("Loaded {}", path.display());
+        Ok(value)
+    }
+}
+
+/// Seconds since the Unix epoch, or 0 on the web, where there's no clock to
+/// ask.
+pub fn unix_timestamp() -> u64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        0
+    }
+}
@@ -0,0 +1,440 @@
+use bevy::color::palettes::tailwind::*;
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, save_to_disk};
+
+use crate::atmosphere::TimeOfDay;
+use crate::camera::CameraMode;
+use crate::director::Director;
+use crate::hud::Notification;
+use crate::locale::Localization;
+use crate::persist::{DataDir, unix_timestamp};
+use crate::text_bubble::TextBubble;
+use crate::ui::UiFont;
+
+pub fn photo_plugin(app: &mut App) {
+    app.add_systems(Update, toggle_photo_mode_on_f10);
+    app.add_systems(OnEnter(PhotoState::On), enter_photo_mode);
+    app.add_systems(OnExit(PhotoState::On), exit_photo_mode);
+    app.add_systems(
+        Update,
+        (
+            adjust_photo_settings,
+            toggle_photo_visibility,
+            take_screenshot_on_enter,
+        )
+            .run_if(in_state(PhotoState::On)),
+    );
+
+    app.insert_state(PhotoState::Off);
+    app.init_resource::<DataDir>();
+}
+
+/// Localization keys used in this module.
+pub const LOCALE_KEYS: &[&str] = &["photo.hint", "notify.screenshot"];
+
+const MIN_FOV: f32 = 0.3;
+const MAX_FOV: f32 = 2.4;
+
+/// Radians of field of view per second while zooming.
+const FOV_RATE: f32 = 0.8;
+
+/// Hours of time of day per second while scrubbing.
+const HOURS_RATE: f32 = 3.0;
+
+/// Pauses the game and hands over a free camera for taking pictures.
+#[derive(States, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum PhotoState {
+    Off,
+    On,
+}
+
+/// What photo mode changed, to put back afterwards.
+#[derive(Resource, Debug)]
+struct PhotoRestore {
+    camera_mode: CameraMode,
+    /// Where a director shot in progress would have handed the camera.
+    handback_mode: Option<CameraMode>,
+    fov: f32,
+    hours: f32,
+}
+
+/// Hidden while in photo mode; holds the visibility to put back.
+#[derive(Component, Debug)]
+struct HiddenForPhoto(Visibility);
+
+#[derive(Component)]
+struct PhotoHint;
+
+fn toggle_photo_mode_on_f10(
+    keys: Res<ButtonInput<KeyCode>>,
+    state: Res<State<PhotoState>>,
+    mut next: ResMut<NextState<PhotoState>>,
+) {
+    if keys.just_pressed(KeyCode::F10) {
+        match state.get() {
+            PhotoState::On => next.set(PhotoState::Off),
+            PhotoState::Off => next.set(PhotoState::On),
+        }
+    }
+}
+
+fn enter_photo_mode(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    loc: Res<Localization>,
+    mut time: ResMut<Time<Virtual>>,
+    mut mode: ResMut<CameraMode>,
+    director: Res<Director>,
+    tod: Res<TimeOfDay>,
+    projection: Single<&Projection, With<Camera3d>>,
+) {
+    let fov = match *projection {
+        Projection::Perspective(p) => p.fov,
+        _ => std::f32::consts::FRAC_PI_2,
+    };
+    commands.insert_resource(PhotoRestore {
+        camera_mode: *mode,
+        handback_mode: director.handback_mode(),
+        fov,
+        hours: tod.hours,
+    });
+
+    time.pause();
+    *mode = CameraMode::FreeFly;
+
+    commands.spawn((
+        PhotoHint,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: px(16.0),
+            width: percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        children![(
+            Node {
+                padding: UiRect::axes(px(12.0), px(6.0)),
+                border_radius: BorderRadius::all(px(8.0)),
+                ..default()
+            },
+            BackgroundColor(ZINC_950.with_alpha(0.5).into()),
+            Text::new(loc.get("photo.hint")),
+            TextFont {
+                font: font.0.clone(),
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    ));
+}
+
+fn exit_photo_mode(
+    mut commands: Commands,
+    restore: Res<PhotoRestore>,
+    mut time: ResMut<Time<Virtual>>,
+    mut mode: ResMut<CameraMode>,
+    director: Res<Director>,
+    mut tod: ResMut<TimeOfDay>,
+    mut projection: Single<&mut Projection, With<Camera3d>>,
+    hidden: Query<(Entity, &HiddenForPhoto)>,
+    hints: Query<Entity, With<PhotoHint>>,
+) {
+    time.unpause();
+    // a director shot cut short by photo mode may have left the game in
+    // slow motion
+    time.set_relative_speed(1.0);
+    // and without a shot, the cinematic camera would sit on its last frame
+    *mode = match restore.camera_mode {
+        CameraMode::Cinematic if director.handback_mode().is_none() => {
+            restore.handback_mode.unwrap_or(CameraMode::Chase)
+        }
+        mode => mode,
+    };
+    // the sky feeds into duck AI, so don't leave it changed
+    tod.hours = restore.hours;
+    if let Projection::Perspective(p) = &mut **projection {
+        p.fov = restore.fov;
+    }
+
+    for (e, hidden) in hidden {
+        commands
+            .entity(e)
+            .insert(hidden.0)
+            .remove::<HiddenForPhoto>();
+    }
+    for e in hints {
+        commands.entity(e).despawn();
+    }
+    commands.remove_resource::<PhotoRestore>();
+}
+
+fn adjust_photo_settings(
+    keys: Res<ButtonInput<KeyCode>>,
+    real_time: Res<Time<Real>>,
+    mut tod: ResMut<TimeOfDay>,
+    mut projection: Single<&mut Projection, With<Camera3d>>,
+) {
+    let dt = real_time.delta_secs();
+
+    let mut zoom = 0.0;
+    if keys.pressed(KeyCode::KeyZ) {
+        zoom -= 1.0;
+    }
+    if keys.pressed(KeyCode::KeyX) {
+        zoom += 1.0;
+    }
+    if zoom != 0.0
+        && let Projection::Perspective(p) = &mut **projection
+    {
+        p.fov = (p.fov + zoom * FOV_RATE * dt).clamp(MIN_FOV, MAX_FOV);
+    }
+
+    let mut scrub = 0.0;
+    if keys.pressed(KeyCode::KeyQ) {
+        scrub -= 1.0;
+    }
+    if keys.pressed(KeyCode::KeyE) {
+        scrub += 1.0;
+    }
+    if scrub != 0.0 {
+        tod.hours = (tod.hours + scrub * HOURS_RATE * dt).rem_euclid(24.0);
+    }
+}
+
+type Hideable<'a> = (Entity, &'a Visibility, Option<&'a HiddenForPhoto>);
+
+fn toggle_hidden(commands: &mut Commands, (e, vis, hidden): Hideable) {
+    match hidden {
+        Some(hidden) => {
+            commands
+                .entity(e)
+                .insert(hidden.0)
+                .remove::<HiddenForPhoto>();
+        }
+        None => {
+            commands
+                .entity(e)
+                .insert((HiddenForPhoto(*vis), Visibility::Hidden));
+        }
+    }
+}
+
+/// U hides the UI, B the text bubbles over the ducks.
+fn toggle_photo_visibility(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    ui_roots: Query<Hideable, (With<Node>, Without<ChildOf>)>,
+    bubbles: Query<Hideable, With<TextBubble>>,
+) {
+    if keys.just_pressed(KeyCode::KeyU) {
+        for root in &ui_roots {
+            toggle_hidden(&mut commands, root);
+        }
+    }
+    if keys.just_pressed(KeyCode::KeyB) {
+        for bubble in &bubbles {
+            toggle_hidden(&mut commands, bubble);
+        }
+    }
+}
+
+fn take_screenshot_on_enter(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    data: Res<DataDir>,
+    mut taken: Local<u32>,
+) {
+    if !keys.just_pressed(KeyCode::Enter) {
+        return;
+    }
+
+    // counted as well, since several shots may be taken in one second, and
+    // the web has no clock at all
+    *taken += 1;
+    let path = data.path(&format!(
+        "screenshots/duck-{}-{}.png",
+        unix_timestamp(),
+        *taken
+    ));
+    if let Some(dir) = path.parent()
+        && let Err(e) = std::fs::create_dir_all(dir)
+    {
+        error!("Failed to create {}: {}", dir.display(), e);
+        return;
+    }
+
+    info!("Saving screenshot to {}", path.display());
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(save_to_disk(path));
+    commands.trigger(Notification::new("notify.screenshot"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::CinematicShot;
+    use crate::director::{CueKind, DirectorCue, director_plugin};
+    use crate::locale::Language;
+    use crate::text_bubble::{SpawnScoreMarker, text_bubble_plugin};
+    use crate::weather::Weather;
+    use bevy::state::app::StatesPlugin;
+
+    fn headless_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin));
+        app.add_plugins((photo_plugin, text_bubble_plugin, director_plugin));
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.init_resource::<Assets<StandardMaterial>>();
+        app.insert_resource(UiFont(Handle::default()));
+        app.insert_resource(Localization::new(Language::English));
+        app.insert_resource(CameraMode::Chase);
+        app.insert_resource(CinematicShot {
+            position: Vec3::ZERO,
+            focus: Vec3::ZERO,
+            cut: false,
+        });
+        app.insert_state(Weather::Clear);
+        app.insert_resource(TimeOfDay {
+            hours: 10.0,
+            hours_per_second: 0.0,
+        });
+        app.world_mut().spawn((
+            Camera3d::default(),
+            Projection::Perspective(PerspectiveProjection {
+                fov: 1.0,
+                ..default()
+            }),
+            Transform::from_xyz(0.0, 10.0, 30.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ));
+        app.update();
+        app
+    }
+
+    /// Taps `key` for one update, then gives state changes an update to
+    /// land.
+    fn tap(app: &mut App, key: KeyCode) {
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(key);
+        app.update();
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.release(key);
+        keys.clear();
+        app.update();
+    }
+
+    fn photo_state(app: &App) -> PhotoState {
+        *app.world().resource::<State<PhotoState>>().get()
+    }
+
+    fn fov(app: &mut App) -> f32 {
+        let mut camera = app
+            .world_mut()
+            .query_filtered::<&Projection, With<Camera3d>>();
+        match camera.single(app.world()).unwrap() {
+            Projection::Perspective(p) => p.fov,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn photo_mode_puts_everything_back() {
+        let mut app = headless_app();
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .set_relative_speed(0.35);
+
+        tap(&mut app, KeyCode::F10);
+        assert_eq!(photo_state(&app), PhotoState::On);
+        assert!(app.world().resource::<Time<Virtual>>().is_paused());
+        assert_eq!(*app.world().resource::<CameraMode>(), CameraMode::FreeFly);
+        let mut hints = app.world_mut().query_filtered::<(), With<PhotoHint>>();
+        assert_eq!(hints.iter(app.world()).count(), 1);
+
+        app.world_mut().resource_mut::<TimeOfDay>().hours = 21.0;
+        let mut camera = app
+            .world_mut()
+            .query_filtered::<&mut Projection, With<Camera3d>>();
+        if let Projection::Perspective(p) = &mut *camera.single_mut(app.world_mut()).unwrap() {
+            p.fov = 2.0;
+        }
+
+        tap(&mut app, KeyCode::F10);
+        assert_eq!(photo_state(&app), PhotoState::Off);
+        let time = app.world().resource::<Time<Virtual>>();
+        assert!(!time.is_paused());
+        assert_eq!(time.relative_speed(), 1.0);
+        assert_eq!(*app.world().resource::<CameraMode>(), CameraMode::Chase);
+        assert_eq!(app.world().resource::<TimeOfDay>().hours, 10.0);
+        assert_eq!(fov(&mut app), 1.0);
+        assert_eq!(hints.iter(app.world()).count(), 0);
+        assert!(!app.world().contains_resource::<PhotoRestore>());
+    }
+
+    #[test]
+    fn photo_mode_during_a_director_shot_hands_the_camera_back() {
+        let mut app = headless_app();
+        *app.world_mut().resource_mut::<CameraMode>() = CameraMode::TopDown;
+        // let the switch settle, or the director reads it as cancelling the shot
+        app.update();
+        let duck = app.world_mut().spawn(Transform::default()).id();
+        app.world_mut().trigger(DirectorCue {
+            kind: CueKind::Reunion,
+            subjects: vec![duck],
+        });
+        app.update();
+        assert_eq!(*app.world().resource::<CameraMode>(), CameraMode::Cinematic);
+
+        // free-flying for the photo cancels the shot
+        tap(&mut app, KeyCode::F10);
+        assert_eq!(*app.world().resource::<CameraMode>(), CameraMode::FreeFly);
+        assert_eq!(app.world().resource::<Director>().handback_mode(), None);
+
+        tap(&mut app, KeyCode::F10);
+        assert_eq!(photo_state(&app), PhotoState::Off);
+        assert_eq!(*app.world().resource::<CameraMode>(), CameraMode::TopDown);
+        assert_eq!(
+            app.world().resource::<Time<Virtual>>().relative_speed(),
+            1.0
+        );
+    }
+
+    #[test]
+    fn ui_and_bubbles_hide_until_photo_mode_ends() {
+        let mut app = headless_app();
+        let ui = app.world_mut().spawn(Node::default()).id();
+        let duck = app.world_mut().spawn(Transform::default()).id();
+        app.world_mut().trigger(SpawnScoreMarker { duck });
+        app.update();
+
+        let mut bubbles = app.world_mut().query_filtered::<Entity, With<TextBubble>>();
+        let bubble = bubbles.single(app.world()).unwrap();
+        let visibility = |app: &App, e: Entity| app.world().get::<Visibility>(e).copied();
+        let ui_before = visibility(&app, ui).expect("UI nodes have a visibility");
+        let bubble_before = visibility(&app, bubble).expect("bubbles have a visibility");
+
+        tap(&mut app, KeyCode::F10);
+        tap(&mut app, KeyCode::KeyU);
+        assert_eq!(visibility(&app, ui), Some(Visibility::Hidden));
+        assert_eq!(visibility(&app, bubble), Some(bubble_before));
+
+        tap(&mut app, KeyCode::KeyB);
+        for _ in 0..5 {
+            app.update();
+        }
+        assert_eq!(visibility(&app, bubble), Some(Visibility::Hidden));
+
+        // toggles back
+        tap(&mut app, KeyCode::KeyB);
+        assert_eq!(visibility(&app, bubble), Some(bubble_before));
+        tap(&mut app, KeyCode::KeyB);
+
+        tap(&mut app, KeyCode::F10);
+        assert_eq!(visibility(&app, ui), Some(ui_before));
+        assert_eq!(visibility(&app, bubble), Some(bubble_before));
+        let mut hidden = app.world_mut().query::<&HiddenForPhoto>();
+        assert_eq!(hidden.iter(app.world()).count(), 0);
+    }
+}
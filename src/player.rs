@@ -1,116 +1,165 @@
-use bevy::{input::mouse::MouseWheel, prelude::*};
+use bevy::prelude::*;
 
+use crate::camera::CameraMode;
 use crate::ducks::{Duck, DuckJump};
 
 pub fn player_plugin(app: &mut App) {
-    app.insert_resource(CameraScale {
-        target: 0.0,
-        actual: 1.0,
-    });
-
-    app.add_systems(
-        Update,
-        (
-            handle_player_keys,
-            update_camera_scale,
-            make_camera_follow_player,
-        )
-            .chain(),
-    );
-}
+    app.add_systems(Update, latch_player_keys);
+    app.add_systems(FixedPreUpdate, sample_player_input);
+    app.add_systems(FixedUpdate, apply_player_input);
 
-#[derive(Resource)]
-struct CameraScale {
-    target: f32,
-    actual: f32,
+    app.insert_resource(PlayerInput::default());
+    app.insert_resource(LatchedKeys::default());
 }
 
-#[derive(Component)]
-pub struct PlayerDuck;
-
-fn camera_transform(player: Transform, scale: f32) -> Transform {
-    let c1 = Vec3::new(2.0, 2.0, -5.0);
-    let c2 = Vec3::new(0.0, 30.0, -15.0);
-
-    let f1 = Vec3::new(0.0, 0.0, 10.0);
-    let f2 = Vec3::new(0.0, 0.0, 15.0);
+/// The player's controls for one fixed tick. Gameplay reads this rather than
+/// the keyboard, so a recorded stream of them can drive a replay.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlayerInput {
+    pub forward: bool,
+    pub back: bool,
+    pub left: bool,
+    pub right: bool,
+    pub boost: bool,
+    pub jump: bool,
+    pub cycle_weather: bool,
+    pub lightning: bool,
+}
 
-    let c = c1.lerp(c2, scale);
-    let f = f1.lerp(f2, scale);
+impl PlayerInput {
+    pub fn to_bits(self) -> u8 {
+        [
+            self.forward,
+            self.back,
+            self.left,
+            self.right,
+            self.boost,
+            self.jump,
+            self.cycle_weather,
+            self.lightning,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (i, b)| bits | ((*b as u8) << i))
+    }
 
-    let mut camera = player * Transform::from_translation(c);
-    let focus_transform = Transform::from_translation(f);
-    let focus = player * focus_transform;
+    pub fn from_bits(bits: u8) -> Self {
+        let bit = |i: u8| bits & (1 << i) != 0;
+        Self {
+            forward: bit(0),
+            back: bit(1),
+            left: bit(2),
+            right: bit(3),
+            boost: bit(4),
+            jump: bit(5),
+            cycle_weather: bit(6),
+            lightning: bit(7),
+        }
+    }
+}
 
-    camera.look_at(focus.translation, Vec3::Y);
+/// Key presses seen since the last fixed tick, which might be several
+/// frames ago, or less than one.
+#[derive(Resource, Debug, Default)]
+pub struct LatchedKeys {
+    jump: bool,
+    cycle_weather: bool,
+    lightning: bool,
+}
 
-    camera
+fn latch_player_keys(keys: Res<ButtonInput<KeyCode>>, mut latched: ResMut<LatchedKeys>) {
+    latched.jump |= keys.just_pressed(KeyCode::Space);
+    latched.cycle_weather |= keys.just_pressed(KeyCode::KeyM);
+    latched.lightning |= keys.pressed(KeyCode::ControlLeft) && keys.just_pressed(KeyCode::KeyL);
 }
 
-fn make_camera_follow_player(
-    player: Single<&Transform, With<PlayerDuck>>,
-    mut camera: Single<&mut Transform, (With<Camera3d>, Without<PlayerDuck>)>,
-    scale: Res<CameraScale>,
+pub fn sample_player_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut latched: ResMut<LatchedKeys>,
+    mut input: ResMut<PlayerInput>,
+    camera: Res<CameraMode>,
 ) {
-    **camera = camera_transform(**player, scale.actual);
+    // the free-flying camera has the keyboard
+    if *camera == CameraMode::FreeFly {
+        *input = PlayerInput::default();
+        *latched = LatchedKeys::default();
+        return;
+    }
+
+    *input = PlayerInput {
+        forward: keys.pressed(KeyCode::KeyW),
+        back: keys.pressed(KeyCode::KeyS),
+        left: keys.pressed(KeyCode::KeyA),
+        right: keys.pressed(KeyCode::KeyD),
+        boost: keys.pressed(KeyCode::ShiftLeft),
+        jump: latched.jump,
+        cycle_weather: latched.cycle_weather,
+        lightning: latched.lightning,
+    };
+    *latched = LatchedKeys::default();
 }
 
-fn update_camera_scale(mut events: MessageReader<MouseWheel>, mut scale: ResMut<CameraScale>) {
-    let mut delta = 0.0;
+#[derive(Component)]
+#[require(Stamina)]
+pub struct PlayerDuck;
 
-    let rate = 0.06;
+/// Boost fuel, in [0, 1]. Drains while boosting and recovers otherwise.
+#[derive(Component, Debug)]
+pub struct Stamina(pub f32);
 
-    for event in events.read() {
-        if event.y < 0.0 {
-            delta += rate;
-        } else {
-            delta -= rate;
-        }
+impl Default for Stamina {
+    fn default() -> Self {
+        Self(1.0)
     }
-
-    scale.target += delta;
-
-    scale.target = scale.target.clamp(0.0, 1.0);
-
-    scale.actual += (scale.target - scale.actual) * 0.05;
 }
 
-fn handle_player_keys(
+const STAMINA_DRAIN: f32 = 0.3;
+const STAMINA_RECOVERY: f32 = 0.15;
+
+fn apply_player_input(
     mut commands: Commands,
-    keys: Res<ButtonInput<KeyCode>>,
-    ducks: Query<(Entity, &mut Duck, &Transform), With<PlayerDuck>>,
+    input: Res<PlayerInput>,
+    ducks: Query<(Entity, &mut Duck, &mut Stamina, &Transform), With<PlayerDuck>>,
+    time: Res<Time>,
 ) {
-    for (e, mut duck, transform) in ducks {
+    for (e, mut duck, mut stamina, transform) in ducks {
         let mut velocity = Vec3::ZERO;
         let mut angular_velocity = 0.0;
 
         // jumping
-        if keys.just_pressed(KeyCode::Space) {
+        if input.jump {
             commands.write_message(DuckJump { duck: e });
         }
 
         // forward
-        if keys.pressed(KeyCode::KeyW) {
+        if input.forward {
             velocity += transform.local_z().as_vec3()
         }
 
         // left
-        if keys.pressed(KeyCode::KeyA) {
+        if input.left {
             angular_velocity += 1.0;
         }
 
         // backward
-        if keys.pressed(KeyCode::KeyS) {
+        if input.back {
             velocity -= transform.local_z().as_vec3()
         }
 
         // right
-        if keys.pressed(KeyCode::KeyD) {
+        if input.right {
             angular_velocity -= 1.0;
         }
 
-        duck.is_kicking = keys.pressed(KeyCode::KeyW);
-        duck.is_boosting = keys.pressed(KeyCode::ShiftLeft);
+        duck.is_kicking = input.forward;
+        duck.is_boosting = input.boost && stamina.0 > 0.0;
+
+        let rate = if duck.is_kicking && duck.is_boosting {
+            -STAMINA_DRAIN
+        } else {
+            STAMINA_RECOVERY
+        };
+        stamina.0 = (stamina.0 + rate * time.delta_secs()).clamp(0.0, 1.0);
 
         duck.angular_acceleration = angular_velocity * 2.0;
     }
@@ -0,0 +1,459 @@
+use bevy::color::palettes::tailwind::*;
+use bevy::prelude::*;
+
+use crate::child_rel::Following;
+use crate::director::{CueKind, DirectorCue};
+use crate::ducks::{Done, Duck, Duckling, TargetPosition};
+use crate::hud::Notification;
+use crate::lake::{LAKE_RADIUS, ReedBed};
+use crate::level::PopulateLevel;
+use crate::math::GameRng;
+use crate::player::PlayerDuck;
+use crate::quacks::QuackKind;
+use crate::text_bubble::Quack;
+
+pub fn predators_plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (scare_off_predators, hunt, move_predators).chain(),
+    );
+
+    app.add_observer(spawn_predators);
+    app.add_observer(on_duckling_caught);
+}
+
+/// Localization keys used in this module.
+pub const LOCALE_KEYS: &[&str] = &["notify.caught"];
+
+const NUM_PIKES: usize = 2;
+const NUM_HERONS: usize = 3;
+
+/// Ducklings notice predators this close, and adults come to defend them.
+pub const DETECT_RADIUS: f32 = 14.0;
+
+/// Adults this close to a predator drive it off; the player from further
+/// away while boosting.
+const SCARE_RADIUS: f32 = 4.0;
+const BOOST_SCARE_RADIUS: f32 = 8.0;
+
+/// Seconds a scared predator keeps away, and rests between attacks.
+const FLEE_TIME: f32 = 6.0;
+const ATTACK_COOLDOWN: f32 = 4.0;
+
+/// Longest a lunge lasts before the predator gives up, in seconds.
+const LUNGE_TIME: f32 = 2.0;
+
+const CATCH_RADIUS: f32 = 1.0;
+
+/// Pikes patrol under this depth, and only go for ducklings near the surface.
+const PIKE_DEPTH: f32 = -0.4;
+const SURFACE_HEIGHT: f32 = 0.5;
+
+/// Herons wade around the edge of the lake, in this band of radii.
+const SHORE_MIN: f32 = LAKE_RADIUS * 0.75;
+const SHORE_MAX: f32 = LAKE_RADIUS * 0.9;
+
+/// Caught ducklings turn up this far from the middle of the lake, off the
+/// reed beds but well clear of the herons.
+const ESCAPE_RADIUS: f32 = SHORE_MIN - 2.0 * DETECT_RADIUS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PredatorKind {
+    /// Patrols under the water and lunges up at ducklings.
+    Pike,
+    /// Stalks the shoreline and strikes at ducklings that come close.
+    Heron,
+}
+
+impl PredatorKind {
+    fn patrol_speed(self) -> f32 {
+        match self {
+            PredatorKind::Pike => 3.0,
+            PredatorKind::Heron => 1.0,
+        }
+    }
+
+    fn lunge_speed(self) -> f32 {
+        match self {
+            PredatorKind::Pike => 12.0,
+            PredatorKind::Heron => 8.0,
+        }
+    }
+
+    /// How close prey has to be for an attack.
+    fn strike_range(self) -> f32 {
+        match self {
+            PredatorKind::Pike => 10.0,
+            PredatorKind::Heron => 6.0,
+        }
+    }
+
+    fn patrol_height(self) -> f32 {
+        match self {
+            PredatorKind::Pike => PIKE_DEPTH,
+            PredatorKind::Heron => 0.0,
+        }
+    }
+
+    fn random_waypoint(self, from: Vec3, rng: &mut GameRng) -> Vec3 {
+        match self {
+            PredatorKind::Pike => {
+                let p = rng.vec(0.0, LAKE_RADIUS * 0.7);
+                Vec3::new(p.x, PIKE_DEPTH, p.y)
+            }
+            PredatorKind::Heron => {
+                // a little way along the shore
+                let a = from.z.atan2(from.x) + rng.range(-0.3..0.3);
+                let r = rng.range(SHORE_MIN..SHORE_MAX);
+                Vec3::new(r * a.cos(), 0.0, r * a.sin())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PredatorState {
+    Patrolling { waypoint: Vec3 },
+    Lunging { prey: Entity, time: f32 },
+    Fleeing { from: Vec3, time: f32 },
+}
+
+#[derive(Component, Debug)]
+pub struct Predator {
+    pub kind: PredatorKind,
+    pub state: PredatorState,
+    /// Seconds until it can attack again.
+    cooldown: f32,
+}
+
+impl Predator {
+    /// Whether it's a danger right now, rather than running away.
+    pub fn is_hunting(&self) -> bool {
+        !matches!(self.state, PredatorState::Fleeing { .. })
+    }
+}
+
+/// A predator got a duckling.
+#[derive(Event, Debug)]
+pub struct DucklingCaught {
+    pub duckling: Entity,
+    pub predator: Entity,
+}
+
+fn spawn_predators(
+    _event: On<PopulateLevel>,
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    existing: Query<Entity, With<Predator>>,
+) {
+    for e in existing {
+        commands.entity(e).despawn();
+    }
+
+    let pike_body = meshes.add(Capsule3d::new(0.35, 2.2));
+    let pike_fin = meshes.add(Cone::new(0.25, 0.7));
+    let pike_material = materials.add(StandardMaterial::from_color(LIME_900));
+
+    let heron_body = meshes.add(Capsule3d::new(0.4, 1.0));
+    let heron_leg = meshes.add(Cylinder::new(0.04, 1.6));
+    let heron_neck = meshes.add(Capsule3d::new(0.1, 1.0));
+    let heron_material = materials.add(StandardMaterial::from_color(SLATE_400));
+
+    let horizontal = Quat::from_rotation_x(std::f32::consts::PI / 2.0);
+
+    for _ in 0..NUM_PIKES {
+        let p = rng.vec(20.0, LAKE_RADIUS * 0.7);
+        let tf = Transform::from_xyz(p.x, PIKE_DEPTH, p.y);
+        commands.spawn((
+            Predator {
+                kind: PredatorKind::Pike,
+                state: PredatorState::Patrolling {
+                    waypoint: PredatorKind::Pike.random_waypoint(tf.translation, &mut rng),
+                },
+                cooldown: 0.0,
+            },
+            tf,
+            Visibility::default(),
+            children![
+                (
+                    Transform::from_rotation(horizontal),
+                    Mesh3d(pike_body.clone()),
+                    MeshMaterial3d(pike_material.clone()),
+                ),
+                // all you see of it from above the water
+                (
+                    Transform::from_xyz(0.0, 0.5, 0.0),
+                    Mesh3d(pike_fin.clone()),
+                    MeshMaterial3d(pike_material.clone()),
+                ),
+            ],
+        ));
+    }
+
+    for i in 0..NUM_HERONS {
+        let a = i as f32 / NUM_HERONS as f32 * std::f32::consts::TAU + rng.range(0.0..1.0);
+        let r = rng.range(SHORE_MIN..SHORE_MAX);
+        let tf = Transform::from_xyz(r * a.cos(), 0.0, r * a.sin());
+        commands.spawn((
+            Predator {
+                kind: PredatorKind::Heron,
+                state: PredatorState::Patrolling {
+                    waypoint: PredatorKind::Heron.random_waypoint(tf.translation, &mut rng),
+                },
+                cooldown: 0.0,
+            },
+            tf,
+            Visibility::default(),
+            children![
+                (
+                    Transform::from_xyz(0.0, 2.0, 0.0).with_rotation(horizontal),
+                    Mesh3d(heron_body.clone()),
+                    MeshMaterial3d(heron_material.clone()),
+                ),
+                (
+                    Transform::from_xyz(0.15, 0.8, 0.0),
+                    Mesh3d(heron_leg.clone()),
+                    MeshMaterial3d(heron_material.clone()),
+                ),
+                (
+                    Transform::from_xyz(-0.15, 0.8, 0.0),
+                    Mesh3d(heron_leg.clone()),
+                    MeshMaterial3d(heron_material.clone()),
+                ),
+                (
+                    Transform::from_xyz(0.0, 2.8, 0.7),
+                    Mesh3d(heron_neck.clone()),
+                    MeshMaterial3d(heron_material.clone()),
+                ),
+            ],
+        ));
+    }
+}
+
+/// Adults chase off predators that come too close; the player can do it
+/// from further away by charging at them.
+fn scare_off_predators(
+    predators: Query<(&Transform, &mut Predator)>,
+    adults: Query<(&Transform, &Duck, Has<PlayerDuck>), Without<Duckling>>,
+) {
+    for (tf, mut predator) in predators {
+        if !predator.is_hunting() {
+            continue;
+        }
+        for (adult_tf, duck, is_player) in &adults {
+            let radius = if is_player && duck.is_boosting {
+                BOOST_SCARE_RADIUS
+            } else {
+                SCARE_RADIUS
+            };
+            let dist = adult_tf.translation.xz().distance(tf.translation.xz());
+            if dist < radius {
+                predator.state = PredatorState::Fleeing {
+                    from: adult_tf.translation,
+                    time: FLEE_TIME,
+                };
+                break;
+            }
+        }
+    }
+}
+
+/// Picks prey, and catches it at the end of a lunge.
+fn hunt(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    predators: Query<(Entity, &Transform, &mut Predator)>,
+    // ducklings back with their families are safe
+    ducklings: Query<(Entity, &Transform), (With<Duckling>, Without<Done>)>,
+    time: Res<Time<Fixed>>,
+) {
+    let dt = time.delta_secs();
+    for (e, tf, mut predator) in predators {
+        predator.cooldown = (predator.cooldown - dt).max(0.0);
+        let kind = predator.kind;
+
+        match predator.state {
+            PredatorState::Patrolling { .. } if predator.cooldown <= 0.0 => {
+                let prey = ducklings
+                    .iter()
+                    .filter(|(_, d)| kind != PredatorKind::Pike || d.translation.y < SURFACE_HEIGHT)
+                    .map(|(d, d_tf)| (d, d_tf.translation.xz().distance(tf.translation.xz())))
+                    .filter(|(_, dist)| *dist < kind.strike_range())
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+                if let Some((prey, _)) = prey {
+                    predator.state = PredatorState::Lunging { prey, time: 0.0 };
+                }
+            }
+            PredatorState::Lunging { prey, time } => {
+                let prey_pos = ducklings.get(prey).ok().map(|(_, d)| d.translation);
+                let caught = prey_pos.is_some_and(|p| p.distance(tf.translation) < CATCH_RADIUS);
+                if caught {
+                    commands.trigger(DucklingCaught {
+                        duckling: prey,
+                        predator: e,
+                    });
+                }
+                if caught || prey_pos.is_none() || time > LUNGE_TIME {
+                    predator.cooldown = ATTACK_COOLDOWN;
+                    predator.state = PredatorState::Patrolling {
+                        waypoint: kind.random_waypoint(tf.translation, &mut rng),
+                    };
+                } else {
+                    predator.state = PredatorState::Lunging {
+                        prey,
+                        time: time + dt,
+                    };
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn move_predators(
+    mut rng: ResMut<GameRng>,
+    predators: Query<(&mut Transform, &mut Predator)>,
+    ducklings: Query<&Transform, (With<Duckling>, Without<Predator>)>,
+    time: Res<Time<Fixed>>,
+) {
+    let dt = time.delta_secs();
+    for (mut tf, mut predator) in predators {
+        let kind = predator.kind;
+        let (goal, speed) = match &mut predator.state {
+            PredatorState::Patrolling { waypoint } => {
+                if tf.translation.xz().distance(waypoint.xz()) < 1.0 {
+                    *waypoint = kind.random_waypoint(tf.translation, &mut rng);
+                }
+                (*waypoint, kind.patrol_speed())
+            }
+            PredatorState::Lunging { prey, .. } => {
+                let Ok(prey) = ducklings.get(*prey) else {
+                    continue;
+                };
+                (prey.translation, kind.lunge_speed())
+            }
+            PredatorState::Fleeing { from, time } => {
+                *time -= dt;
+                if *time <= 0.0 {
+                    predator.state = PredatorState::Patrolling {
+                        waypoint: kind.random_waypoint(tf.translation, &mut rng),
+                    };
+                    continue;
+                }
+                let away = (tf.translation - *from).with_y(0.0).normalize_or_zero();
+                (tf.translation + away * 10.0, kind.lunge_speed() * 0.5)
+            }
+        };
+
+        // pikes surface to strike, then sink back down
+        let height = match predator.state {
+            PredatorState::Lunging { .. } => goal.y.min(0.0),
+            _ => kind.patrol_height(),
+        };
+        let goal = goal.with_y(height);
+
+        let delta = goal - tf.translation;
+        let step = delta.clamp_length_max(speed * dt);
+        tf.translation += step;
+
+        // keep pikes in the water and herons off the open lake
+        let r = tf.translation.xz().length();
+        let max_r = match kind {
+            PredatorKind::Pike => LAKE_RADIUS * 0.95,
+            PredatorKind::Heron => LAKE_RADIUS,
+        };
+        if r > max_r {
+            let xz = tf.translation.xz() * max_r / r;
+            tf.translation.x = xz.x;
+            tf.translation.z = xz.y;
+        }
+
+        let heading = step.with_y(0.0);
+        if heading.length_squared() > 1e-6 {
+            let target = tf.translation + heading;
+            tf.look_at(target, Vec3::Y);
+            // meshes face +z, like the ducks
+            tf.rotate_local_y(std::f32::consts::PI);
+        }
+    }
+}
+
+/// The duckling escapes, shaken, and turns up in open water off one of the
+/// reed beds.
+fn on_duckling_caught(
+    event: On<DucklingCaught>,
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut ducklings: Query<(&mut Transform, &mut Duck, Option<&mut TargetPosition>), With<Duckling>>,
+    reeds: Query<&Transform, (With<ReedBed>, Without<Duckling>)>,
+) {
+    let Ok((mut tf, mut duck, target)) = ducklings.get_mut(event.duckling) else {
+        return;
+    };
+
+    let nests: Vec<Vec3> = reeds.iter().map(|r| r.translation).collect();
+    if !nests.is_empty() {
+        let nest = nests[rng.range(0..nests.len())].with_y(0.0);
+        // the reeds themselves are where the herons wade
+        let escape = nest.normalize_or_zero() * ESCAPE_RADIUS;
+        tf.translation = escape;
+        duck.velocity = Vec3::ZERO;
+        if let Some(mut target) = target {
+            target.pos = escape;
+        }
+    }
+
+    info!(
+        "Duckling {} was caught by {}",
+        event.duckling, event.predator
+    );
+    commands.entity(event.duckling).remove::<Following>();
+    commands.write_message(Quack::new(event.duckling, QuackKind::Panicked));
+    commands.trigger(Notification::new("notify.caught"));
+    // to show where it got to
+    commands.trigger(DirectorCue {
+        kind: CueKind::Caught,
+        subjects: vec![event.duckling],
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caught_ducklings_escape_out_of_the_herons_reach() {
+        let mut app = App::new();
+        app.add_observer(on_duckling_caught);
+        app.add_message::<Quack>();
+        app.init_resource::<GameRng>();
+
+        let player = app.world_mut().spawn(PlayerDuck).id();
+        let heron = app.world_mut().spawn_empty().id();
+        app.world_mut()
+            .spawn((ReedBed, Transform::from_xyz(0.0, 0.0, SHORE_MAX)));
+        let duckling = app
+            .world_mut()
+            .spawn((
+                Duckling,
+                Duck::default(),
+                Following(player),
+                Transform::from_xyz(0.0, 0.0, SHORE_MAX),
+            ))
+            .id();
+
+        app.world_mut().trigger(DucklingCaught {
+            duckling,
+            predator: heron,
+        });
+        app.update();
+
+        let p = app.world().get::<Transform>(duckling).unwrap().translation;
+        let r = p.xz().length();
+        assert!(r < SHORE_MIN - PredatorKind::Heron.strike_range(), "{}", r);
+        assert!(p.z > 0.0, "should turn up off the reeds, was at {}", p);
+        assert!(app.world().get::<Following>(duckling).is_none());
+    }
+}
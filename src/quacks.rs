@@ -0,0 +1,172 @@
+use bevy::color::palettes::tailwind::*;
+use bevy::prelude::*;
+
+use crate::math::cosmetic_random_range;
+
+pub fn quacks_plugin(app: &mut App) {
+    app.add_systems(Startup, preload_quack_sounds);
+}
+
+/// What a duck means by a quack. Picks the sounds, the words and how the
+/// text bubble looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuackKind {
+    Contented,
+    Lost,
+    Panicked,
+    Calling,
+    Celebrating,
+    Scolding,
+    Musing,
+}
+
+/// Catalogue entry for a [`QuackKind`].
+#[derive(Debug)]
+pub struct QuackSpec {
+    /// Audio files, relative to the assets folder.
+    pub sounds: &'static [&'static str],
+    /// Localization keys, one of which is picked per quack.
+    pub texts: &'static [&'static str],
+    /// Multiplies the quacking duck's own pitch.
+    pub pitch: f32,
+    pub color: Srgba,
+    pub scale: f32,
+    /// Seconds the text bubble stays up, fades included.
+    pub lifetime: f32,
+    /// Higher priority quacks get a voice first when too many ducks are
+    /// quacking at once.
+    pub priority: u8,
+}
+
+impl QuackKind {
+    pub const ALL: [QuackKind; 7] = [
+        QuackKind::Contented,
+        QuackKind::Lost,
+        QuackKind::Panicked,
+        QuackKind::Calling,
+        QuackKind::Celebrating,
+        QuackKind::Scolding,
+        QuackKind::Musing,
+    ];
+
+    pub fn spec(&self) -> &'static QuackSpec {
+        match self {
+            QuackKind::Contented => &CONTENTED,
+            QuackKind::Lost => &LOST,
+            QuackKind::Panicked => &PANICKED,
+            QuackKind::Calling => &CALLING,
+            QuackKind::Celebrating => &CELEBRATING,
+            QuackKind::Scolding => &SCOLDING,
+            QuackKind::Musing => &MUSING,
+        }
+    }
+
+    pub fn random_text_key(&self) -> &'static str {
+        let texts = self.spec().texts;
+        texts[cosmetic_random_range(0..texts.len())]
+    }
+}
+
+const CONTENTED: QuackSpec = QuackSpec {
+    sounds: &["wek1.ogg", "wek2.ogg"],
+    texts: &["quack.contented.1", "quack.contented.2"],
+    pitch: 1.0,
+    color: Srgba::new(1.0, 1.0, 1.0, 0.4),
+    scale: 0.17,
+    lifetime: 1.5,
+    priority: 0,
+};
+
+const LOST: QuackSpec = QuackSpec {
+    sounds: &["wek3.ogg", "wek4.ogg"],
+    texts: &["quack.lost.1", "quack.lost.2", "quack.lost.3"],
+    pitch: 1.05,
+    color: Srgba::new(1.0, 1.0, 1.0, 0.7),
+    scale: 0.2,
+    lifetime: 2.5,
+    priority: 1,
+};
+
+const PANICKED: QuackSpec = QuackSpec {
+    sounds: &["wek5.ogg", "wek4.ogg"],
+    texts: &["quack.panicked.1", "quack.panicked.2"],
+    pitch: 1.25,
+    color: Srgba::WHITE,
+    scale: 0.23,
+    lifetime: 1.5,
+    priority: 3,
+};
+
+const CALLING: QuackSpec = QuackSpec {
+    sounds: &["wek2.ogg", "wek3.ogg"],
+    texts: &["quack.calling.1", "quack.calling.2"],
+    pitch: 0.95,
+    color: AMBER_200,
+    scale: 0.25,
+    lifetime: 2.5,
+    priority: 2,
+};
+
+const CELEBRATING: QuackSpec = QuackSpec {
+    sounds: &["wek1.ogg", "wek5.ogg"],
+    texts: &["quack.celebrating.1", "quack.celebrating.2"],
+    pitch: 1.15,
+    color: Srgba::WHITE,
+    scale: 0.23,
+    lifetime: 2.0,
+    priority: 2,
+};
+
+const SCOLDING: QuackSpec = QuackSpec {
+    sounds: &["wek4.ogg"],
+    texts: &["quack.scolding.1"],
+    pitch: 0.9,
+    color: BLUE_700,
+    scale: 0.4,
+    lifetime: 3.0,
+    priority: 3,
+};
+
+const MUSING: QuackSpec = QuackSpec {
+    sounds: &["wek2.ogg"],
+    texts: &["quack.musing.1"],
+    pitch: 0.9,
+    color: Srgba::new(1.0, 1.0, 1.0, 0.4),
+    scale: 0.17,
+    lifetime: 4.0,
+    priority: 0,
+};
+
+/// Keeps every catalogue sound loaded, so the first quack of each kind
+/// doesn't stall, and missing files show up in the log at startup.
+#[derive(Resource)]
+struct QuackSounds(#[allow(unused)] Vec<Handle<AudioSource>>);
+
+fn preload_quack_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = QuackKind::ALL
+        .iter()
+        .flat_map(|kind| kind.spec().sounds)
+        .map(|path| asset_server.load(*path))
+        .collect();
+
+    commands.insert_resource(QuackSounds(handles));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn every_quack_sound_exists() {
+        let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        for kind in QuackKind::ALL {
+            let spec = kind.spec();
+            assert!(!spec.sounds.is_empty(), "{kind:?} has no sounds");
+            assert!(!spec.texts.is_empty(), "{kind:?} has no texts");
+            for sound in spec.sounds {
+                assert!(assets.join(sound).exists(), "{kind:?}: missing {sound}");
+            }
+        }
+    }
+}
@@ -0,0 +1,504 @@
+use bevy::ecs::schedule::ExecutorKind;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::ducks::{Done, Duck};
+use crate::hud::Notification;
+use crate::level::{Level, StartLevel};
+use crate::persist::{DataDir, unix_timestamp};
+use crate::player::{PlayerInput, sample_player_input};
+use crate::score::Score;
+
+pub fn replay_plugin(app: &mut App) {
+    app.add_systems(Update, handle_replay_keys);
+    app.add_systems(
+        FixedPreUpdate,
+        (play_back_input, run_state_transitions)
+            .chain()
+            .after(sample_player_input),
+    );
+    app.add_systems(FixedPostUpdate, (record_input, advance_playback));
+
+    // Systems the multi-threaded executor is free to reorder would draw on
+    // the game RNG in a different order from one run to the next.
+    app.edit_schedule(FixedUpdate, |schedule| {
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    });
+
+    app.insert_resource(ReplayState::Idle);
+    app.init_resource::<DataDir>();
+}
+
+/// Localization keys used in this module.
+pub const LOCALE_KEYS: &[&str] = &[
+    "notify.recording",
+    "notify.playing",
+    "notify.recorded",
+    "notify.replay_matched",
+    "notify.replay_diverged",
+];
+
+const REPLAY_VERSION: u32 = 1;
+
+const LAST_REPLAY: &str = "replays/last.ron";
+
+/// How far the scrub keys jump, in fixed ticks.
+const SCRUB_TICKS: u32 = 64 * 10;
+
+const FAST_FORWARD_SPEED: f32 = 4.0;
+const SEEK_SPEED: f32 = 16.0;
+
+/// A recorded session: the level seed and the player's input on every fixed
+/// tick. Everything else follows from those, as long as the simulation only
+/// runs in the fixed schedules and draws on the game RNG.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    /// Run-length encoded [`PlayerInput`] bits, as (bits, number of ticks).
+    pub inputs: Vec<(u8, u32)>,
+    /// [`world_checksum`] after the last tick.
+    pub end_state: u64,
+}
+
+impl Replay {
+    fn encode(seed: u64, ticks: &[u8], end_state: u64) -> Self {
+        let mut inputs: Vec<(u8, u32)> = Vec::new();
+        for &bits in ticks {
+            match inputs.last_mut() {
+                Some((last, n)) if *last == bits => *n += 1,
+                _ => inputs.push((bits, 1)),
+            }
+        }
+        Self {
+            version: REPLAY_VERSION,
+            seed,
+            inputs,
+            end_state,
+        }
+    }
+
+    fn decode(&self) -> Vec<u8> {
+        self.inputs
+            .iter()
+            .flat_map(|&(bits, n)| std::iter::repeat_n(bits, n as usize))
+            .collect()
+    }
+}
+
+#[derive(Resource, Debug)]
+pub enum ReplayState {
+    Idle,
+    Recording {
+        seed: u64,
+        ticks: Vec<u8>,
+        stop: bool,
+    },
+    Playing {
+        replay: Replay,
+        ticks: Vec<u8>,
+        tick: u32,
+        /// Fast-forwarding until this tick.
+        seek_to: Option<u32>,
+        fast_forward: bool,
+    },
+}
+
+impl ReplayState {
+    fn play(replay: Replay) -> Self {
+        ReplayState::Playing {
+            ticks: replay.decode(),
+            replay,
+            tick: 0,
+            seek_to: None,
+            fast_forward: false,
+        }
+    }
+}
+
+/// Reads a replay, refusing ones recorded by another version, which would
+/// only play out differently.
+fn load_replay(path: &Path) -> Result<Replay> {
+    let s = std::fs::read_to_string(path)?;
+    let replay: Replay = ron::from_str(&s)?;
+    if replay.version != REPLAY_VERSION {
+        return Err(format!(
+            "{} is version {}, expected {}",
+            path.display(),
+            replay.version,
+            REPLAY_VERSION
+        )
+        .into());
+    }
+    Ok(replay)
+}
+
+/// Present when the app was started to check a replay and then quit.
+#[derive(Resource, Debug)]
+struct VerifyReplay;
+
+/// Sets the app up to play `path` as fast as possible, without a window,
+/// and exit with an error if it doesn't end in the recorded state.
+pub fn verify_replay(app: &mut App, path: &str) -> Result {
+    let replay = load_replay(Path::new(path))?;
+    play_one_tick_per_update(app, replay);
+    app.insert_resource(VerifyReplay);
+    Ok(())
+}
+
+/// Plays `replay` from the start, advancing one fixed tick per update at
+/// normal speed however long the updates take.
+fn play_one_tick_per_update(app: &mut App, replay: Replay) {
+    let timestep = Time::<Fixed>::default().timestep();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+
+    app.insert_resource(Level {
+        seed: replay.seed,
+        ..Level::LAKE
+    });
+    app.insert_resource(ReplayState::play(replay));
+}
+
+/// Hash of the parts of the world a replay must reproduce exactly.
+fn world_checksum(ducks: &Query<(&Transform, Has<Done>), With<Duck>>, score: &Score) -> u64 {
+    let mut states: Vec<[u32; 4]> = ducks
+        .iter()
+        .map(|(tf, done)| {
+            let p = tf.translation;
+            [p.x.to_bits(), p.y.to_bits(), p.z.to_bits(), done as u32]
+        })
+        .collect();
+    states.sort();
+
+    // FNV-1a, which unlike the std hasher is the same on every build
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |x: u32| {
+        for byte in x.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+    for state in states {
+        state.into_iter().for_each(&mut feed);
+    }
+    feed(score.total() as u32);
+    hash
+}
+
+fn handle_replay_keys(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut state: ResMut<ReplayState>,
+    mut level: ResMut<Level>,
+    mut time: ResMut<Time<Virtual>>,
+    data: Res<DataDir>,
+) {
+    if keys.just_pressed(KeyCode::F6) {
+        match &mut *state {
+            ReplayState::Idle => {
+                *state = ReplayState::Recording {
+                    seed: level.seed,
+                    ticks: Vec::new(),
+                    stop: false,
+                };
+                commands.trigger(StartLevel);
+                commands.trigger(Notification::new("notify.recording"));
+            }
+            ReplayState::Recording { stop, .. } => *stop = true,
+            ReplayState::Playing { .. } => (),
+        }
+    }
+
+    if keys.just_pressed(KeyCode::F7) {
+        match &*state {
+            ReplayState::Idle => match load_replay(&data.path(LAST_REPLAY)) {
+                Ok(replay) => {
+                    level.seed = replay.seed;
+                    *state = ReplayState::play(replay);
+                    commands.trigger(StartLevel);
+                    commands.trigger(Notification::new("notify.playing"));
+                }
+                Err(e) => error!("Failed to load replay: {}", e),
+            },
+            ReplayState::Playing { .. } => {
+                *state = ReplayState::Idle;
+                time.set_relative_speed(1.0);
+            }
+            ReplayState::Recording { .. } => (),
+        }
+    }
+
+    let ReplayState::Playing {
+        tick,
+        seek_to,
+        fast_forward,
+        ..
+    } = &mut *state
+    else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::Backslash) {
+        *fast_forward = !*fast_forward;
+    }
+
+    if keys.just_pressed(KeyCode::BracketRight) {
+        *seek_to = Some(*tick + SCRUB_TICKS);
+    }
+
+    // there's no going back, so restart and fast-forward to the earlier tick
+    if keys.just_pressed(KeyCode::BracketLeft) {
+        *seek_to = Some(tick.saturating_sub(SCRUB_TICKS));
+        *tick = 0;
+        commands.trigger(StartLevel);
+    }
+
+    let speed = if seek_to.is_some() {
+        SEEK_SPEED
+    } else if *fast_forward {
+        FAST_FORWARD_SPEED
+    } else {
+        1.0
+    };
+    time.set_relative_speed(speed);
+}
+
+fn play_back_input(state: Res<ReplayState>, mut input: ResMut<PlayerInput>) {
+    if let ReplayState::Playing { ticks, tick, .. } = &*state {
+        let bits = ticks.get(*tick as usize).copied().unwrap_or(0);
+        *input = PlayerInput::from_bits(bits);
+    }
+}
+
+/// States normally change once per frame, but a frame may hold any number of
+/// fixed ticks, so apply changes made by the simulation before the next one.
+fn run_state_transitions(world: &mut World) {
+    world.run_schedule(StateTransition);
+}
+
+fn record_input(
+    mut commands: Commands,
+    mut state: ResMut<ReplayState>,
+    input: Res<PlayerInput>,
+    ducks: Query<(&Transform, Has<Done>), With<Duck>>,
+    score: Res<Score>,
+    data: Res<DataDir>,
+) {
+    let ReplayState::Recording { seed, ticks, stop } = &mut *state else {
+        return;
+    };
+
+    ticks.push(input.to_bits());
+    if !*stop {
+        return;
+    }
+
+    let replay = Replay::encode(*seed, ticks, world_checksum(&ducks, &score));
+    info!("Recorded {} ticks", ticks.len());
+    *state = ReplayState::Idle;
+
+    let archived = format!("replays/{}.ron", unix_timestamp());
+    let saved = data
+        .save_ron(&archived, &replay)
+        .and_then(|_| data.save_ron(LAST_REPLAY, &replay));
+    match saved {
+        Ok(()) => commands.trigger(Notification::new("notify.recorded")),
+        Err(e) => error!("Failed to save replay: {}", e),
+    }
+}
+
+fn advance_playback(
+    mut commands: Commands,
+    mut state: ResMut<ReplayState>,
+    mut time: ResMut<Time<Virtual>>,
+    ducks: Query<(&Transform, Has<Done>), With<Duck>>,
+    score: Res<Score>,
+    verify: Option<Res<VerifyReplay>>,
+) {
+    let ReplayState::Playing {
+        replay,
+        ticks,
+        tick,
+        seek_to,
+        ..
+    } = &mut *state
+    else {
+        return;
+    };
+
+    *tick += 1;
+    if seek_to.is_some_and(|t| *tick >= t) {
+        *seek_to = None;
+    }
+
+    if (*tick as usize) < ticks.len() {
+        return;
+    }
+
+    let end_state = world_checksum(&ducks, &score);
+    let matches = end_state == replay.end_state;
+    if matches {
+        info!("Replay finished in the recorded state");
+    } else {
+        error!(
+            "Replay diverged: ended in state {:016x}, recorded {:016x}",
+            end_state, replay.end_state
+        );
+    }
+
+    *state = ReplayState::Idle;
+    time.set_relative_speed(1.0);
+
+    if verify.is_some() {
+        commands.write_message(if matches {
+            AppExit::Success
+        } else {
+            AppExit::error()
+        });
+    } else if matches {
+        commands.trigger(Notification::new("notify.replay_matched"));
+    } else {
+        commands.trigger(Notification::new("notify.replay_diverged"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::log::LogPlugin;
+
+    /// Swims about, boosting now and then, and changes the weather to a
+    /// thunderstorm halfway through.
+    fn inputs() -> Vec<u8> {
+        (0..400u32)
+            .map(|t| {
+                PlayerInput {
+                    forward: t % 200 < 150,
+                    left: t % 90 < 30,
+                    right: t % 130 < 20,
+                    boost: t % 70 < 10,
+                    jump: t % 150 == 0,
+                    cycle_weather: t == 100 || t == 200,
+                    lightning: t == 300,
+                    ..default()
+                }
+                .to_bits()
+            })
+            .collect()
+    }
+
+    /// Keeps whatever the game writes out of the player's own data.
+    fn game_app(data: &DataDir) -> App {
+        let mut app = App::new();
+        app.add_plugins(crate::default_plugins(true).disable::<LogPlugin>());
+        crate::add_game_plugins(&mut app);
+        app.insert_resource(data.clone());
+        app
+    }
+
+    fn scratch_dir(name: &str) -> DataDir {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        DataDir(dir)
+    }
+
+    /// Slow motion from the camera director can stretch a tick over several
+    /// updates.
+    fn play_to_the_end(app: &mut App, ticks: usize) {
+        let mut updates = 0;
+        while !matches!(app.world().resource::<ReplayState>(), ReplayState::Idle) {
+            assert!(updates < ticks * 4, "replay never finished");
+            app.update();
+            updates += 1;
+        }
+    }
+
+    fn play(replay: Replay) -> u64 {
+        let ticks = replay.decode().len();
+        let mut app = game_app(&scratch_dir("replays-play-out"));
+        play_one_tick_per_update(&mut app, replay);
+        app.finish();
+        app.cleanup();
+        play_to_the_end(&mut app, ticks);
+
+        app.world_mut()
+            .run_system_once(|ducks: Query<_, With<Duck>>, score: Res<Score>| {
+                world_checksum(&ducks, &score)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn replays_play_out_the_same_every_time() {
+        let replay = Replay::encode(Level::LAKE.seed, &inputs(), 0);
+        let first = play(replay.clone());
+        let second = play(replay);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn recordings_play_back_into_the_recorded_state() {
+        let data = scratch_dir("recordings-play-back");
+        let mut app = game_app(&data);
+        let timestep = Time::<Fixed>::default().timestep();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+        app.insert_resource(ReplayState::Recording {
+            seed: Level::LAKE.seed,
+            ticks: Vec::new(),
+            stop: false,
+        });
+        app.finish();
+        app.cleanup();
+
+        // held keys only, since the rest are latched from presses
+        for bits in inputs() {
+            let input = PlayerInput::from_bits(bits);
+            let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            keys.release_all();
+            for (held, key) in [
+                (input.forward, KeyCode::KeyW),
+                (input.left, KeyCode::KeyA),
+                (input.right, KeyCode::KeyD),
+                (input.boost, KeyCode::ShiftLeft),
+            ] {
+                if held {
+                    keys.press(key);
+                }
+            }
+            app.update();
+        }
+        if let ReplayState::Recording { stop, .. } = &mut *app.world_mut().resource_mut() {
+            *stop = true;
+        }
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release_all();
+        let mut updates = 0;
+        while !matches!(app.world().resource::<ReplayState>(), ReplayState::Idle) {
+            assert!(updates < 100, "recording never stopped");
+            app.update();
+            updates += 1;
+        }
+
+        // through the file, as F7 would load it
+        let replay = load_replay(&data.path(LAST_REPLAY)).unwrap();
+        std::fs::remove_dir_all(&data.0).unwrap();
+        let ticks = replay.decode();
+        assert!(ticks.len() >= inputs().len() / 2, "{} ticks", ticks.len());
+        assert!(
+            ticks
+                .iter()
+                .any(|&bits| PlayerInput::from_bits(bits).forward)
+        );
+
+        let mut app = game_app(&data);
+        play_one_tick_per_update(&mut app, replay);
+        app.insert_resource(VerifyReplay);
+        app.finish();
+        app.cleanup();
+        play_to_the_end(&mut app, ticks.len());
+        assert_eq!(app.should_exit(), Some(AppExit::Success));
+    }
+}
@@ -0,0 +1,647 @@
+use bevy::ecs::query::QueryData;
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::atmosphere::TimeOfDay;
+use crate::child_rel::{DuckParent, Following};
+use crate::clouds::NoiseOffset;
+use crate::ducks::{AddDuck, Done, Duck, Duckling, FamilyColor, TargetPosition};
+use crate::growth::{GrownUp, Growth, Hatched, Nesting};
+use crate::hud::{GameClock, Notification};
+use crate::persist::DataDir;
+use crate::player::{PlayerDuck, Stamina};
+use crate::score::Score;
+use crate::voice::Voice;
+use crate::weather::Weather;
+
+pub fn savegame_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            snapshot_game
+                .pipe(write_save)
+                .run_if(input_just_pressed(KeyCode::F5)),
+            read_save
+                .pipe(restore_game)
+                .run_if(input_just_pressed(KeyCode::F9)),
+        ),
+    );
+
+    app.init_resource::<DataDir>();
+}
+
+/// Localization keys used in this module.
+pub const LOCALE_KEYS: &[&str] = &["notify.saved", "notify.no_save", "notify.loaded"];
+
+const SAVE_FILE: &str = "savegame.ron";
+
+/// Bumped whenever the format changes; older saves are refused.
+const SAVE_VERSION: u32 = 2;
+
+/// Everything needed to put the world back the way it was. Engine types are
+/// stored as plain arrays so the file doesn't depend on their serde layout.
+#[derive(Debug, Serialize, Deserialize)]
+struct SaveGame {
+    version: u32,
+    elapsed_secs: f32,
+    /// [`TimeOfDay::hours`].
+    time_of_day: f32,
+    weather: SavedWeather,
+    cloud_offset: [f32; 2],
+    score: Score,
+    ducks: Vec<SavedDuck>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum SavedWeather {
+    Clear,
+    Fog,
+    Thunderstorm,
+}
+
+impl From<Weather> for SavedWeather {
+    fn from(weather: Weather) -> Self {
+        match weather {
+            Weather::Clear => SavedWeather::Clear,
+            Weather::Fog => SavedWeather::Fog,
+            Weather::Thunderstorm => SavedWeather::Thunderstorm,
+        }
+    }
+}
+
+impl From<SavedWeather> for Weather {
+    fn from(weather: SavedWeather) -> Self {
+        match weather {
+            SavedWeather::Clear => Weather::Clear,
+            SavedWeather::Fog => Weather::Fog,
+            SavedWeather::Thunderstorm => Weather::Thunderstorm,
+        }
+    }
+}
+
+/// Ducks refer to each other by their index in [`SaveGame::ducks`].
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SavedDuck {
+    is_player: bool,
+    is_child: bool,
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    scale: [f32; 3],
+    physics: SavedPhysics,
+    target: Option<[f32; 3]>,
+    parent: Option<usize>,
+    following: Option<usize>,
+    done: bool,
+    voice: Option<SavedVoice>,
+    family_color: Option<[f32; 4]>,
+    stamina: Option<f32>,
+    #[serde(default)]
+    growth: Option<SavedGrowth>,
+    /// [`GrownUp::reunited`], for ducklings that have grown up.
+    #[serde(default)]
+    grown_up: Option<bool>,
+    /// [`Nesting::left`], for grown-ups expecting a brood.
+    #[serde(default)]
+    nesting: Option<f32>,
+    #[serde(default)]
+    hatched: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SavedGrowth {
+    age: f32,
+    size: f32,
+    speed: f32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SavedPhysics {
+    is_kicking: bool,
+    is_boosting: bool,
+    speed_mod: f32,
+    velocity: [f32; 3],
+    angular_acceleration: f32,
+    angular_velocity: f32,
+    target_head_angle: f32,
+    actual_head_angle: f32,
+    above_sea_level: f32,
+}
+
+impl From<&Duck> for SavedPhysics {
+    fn from(duck: &Duck) -> Self {
+        Self {
+            is_kicking: duck.is_kicking,
+            is_boosting: duck.is_boosting,
+            speed_mod: duck.speed_mod,
+            velocity: duck.velocity.to_array(),
+            angular_acceleration: duck.angular_acceleration,
+            angular_velocity: duck.angular_velocity,
+            target_head_angle: duck.target_head_angle,
+            actual_head_angle: duck.actual_head_angle,
+            above_sea_level: duck.above_sea_level,
+        }
+    }
+}
+
+impl From<&SavedPhysics> for Duck {
+    fn from(saved: &SavedPhysics) -> Self {
+        Self {
+            is_kicking: saved.is_kicking,
+            is_boosting: saved.is_boosting,
+            speed_mod: saved.speed_mod,
+            velocity: Vec3::from_array(saved.velocity),
+            angular_acceleration: saved.angular_acceleration,
+            angular_velocity: saved.angular_velocity,
+            target_head_angle: saved.target_head_angle,
+            actual_head_angle: saved.actual_head_angle,
+            above_sea_level: saved.above_sea_level,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SavedVoice {
+    sample: usize,
+    base_pitch: f32,
+    pitch_jitter: f32,
+    volume: f32,
+}
+
+impl From<&Voice> for SavedVoice {
+    fn from(voice: &Voice) -> Self {
+        Self {
+            sample: voice.sample,
+            base_pitch: voice.base_pitch,
+            pitch_jitter: voice.pitch_jitter,
+            volume: voice.volume,
+        }
+    }
+}
+
+impl From<&SavedVoice> for Voice {
+    fn from(saved: &SavedVoice) -> Self {
+        Self {
+            sample: saved.sample,
+            base_pitch: saved.base_pitch,
+            pitch_jitter: saved.pitch_jitter,
+            volume: saved.volume,
+        }
+    }
+}
+
+#[derive(QueryData)]
+struct DuckData {
+    entity: Entity,
+    transform: &'static Transform,
+    duck: &'static Duck,
+    is_player: Has<PlayerDuck>,
+    is_child: Has<Duckling>,
+    done: Has<Done>,
+    target: Option<&'static TargetPosition>,
+    parent: Option<&'static DuckParent>,
+    following: Option<&'static Following>,
+    voice: Option<&'static Voice>,
+    family_color: Option<&'static FamilyColor>,
+    stamina: Option<&'static Stamina>,
+    growth: Option<&'static Growth>,
+    grown_up: Option<&'static GrownUp>,
+    nesting: Option<&'static Nesting>,
+    hatched: Has<Hatched>,
+}
+
+fn snapshot_game(
+    clock: Res<GameClock>,
+    tod: Res<TimeOfDay>,
+    weather: Res<State<Weather>>,
+    offset: Res<NoiseOffset>,
+    score: Res<Score>,
+    ducks: Query<DuckData>,
+) -> SaveGame {
+    let index: HashMap<Entity, usize> = ducks
+        .iter()
+        .enumerate()
+        .map(|(i, d)| (d.entity, i))
+        .collect();
+
+    let ducks = ducks
+        .iter()
+        .map(|d| SavedDuck {
+            is_player: d.is_player,
+            is_child: d.is_child,
+            translation: d.transform.translation.to_array(),
+            rotation: d.transform.rotation.to_array(),
+            scale: d.transform.scale.to_array(),
+            physics: d.duck.into(),
+            target: d.target.map(|t| t.pos.to_array()),
+            parent: d.parent.and_then(|p| index.get(&p.0).copied()),
+            following: d.following.and_then(|f| index.get(&f.0).copied()),
+            done: d.done,
+            voice: d.voice.map(SavedVoice::from),
+            family_color: d.family_color.map(|c| c.0.to_srgba().to_f32_array()),
+            stamina: d.stamina.map(|s| s.0),
+            growth: d.growth.map(|g| SavedGrowth {
+                age: g.age,
+                size: g.size,
+                speed: g.speed,
+            }),
+            grown_up: d.grown_up.map(|g| g.reunited),
+            nesting: d.nesting.map(|n| n.left),
+            hatched: d.hatched,
+        })
+        .collect();
+
+    SaveGame {
+        version: SAVE_VERSION,
+        elapsed_secs: clock.0.elapsed_secs(),
+        time_of_day: tod.hours,
+        weather: (**weather).into(),
+        cloud_offset: offset.0.to_array(),
+        score: score.clone(),
+        ducks,
+    }
+}
+
+fn write_save(In(save): In<SaveGame>, mut commands: Commands, data: Res<DataDir>) {
+    match data.save_ron(SAVE_FILE, &save) {
+        Ok(()) => commands.trigger(Notification::new("notify.saved")),
+        Err(e) => error!("Failed to save game: {}", e),
+    }
+}
+
+fn check_version(save: SaveGame) -> Result<SaveGame> {
+    if save.version != SAVE_VERSION {
+        return Err(format!(
+            "{} is version {}, expected {}",
+            SAVE_FILE, save.version, SAVE_VERSION
+        )
+        .into());
+    }
+    Ok(save)
+}
+
+/// `None` if there's no save, or none this version can load.
+fn read_save(mut commands: Commands, data: Res<DataDir>) -> Option<SaveGame> {
+    match data.load_ron(SAVE_FILE).and_then(check_version) {
+        Ok(save) => Some(save),
+        Err(e) => {
+            warn!("Can't load game: {}", e);
+            commands.trigger(Notification::new("notify.no_save"));
+            None
+        }
+    }
+}
+
+/// Present while a save is being put back, so the ducks reappearing aren't
+/// mistaken for things happening in the game.
+#[derive(Resource, Debug)]
+pub struct RestoringSave;
+
+fn restore_game(
+    In(save): In<Option<SaveGame>>,
+    mut commands: Commands,
+    mut clock: ResMut<GameClock>,
+    mut tod: ResMut<TimeOfDay>,
+    mut next_weather: ResMut<NextState<Weather>>,
+    mut offset: ResMut<NoiseOffset>,
+    existing: Query<Entity, With<Duck>>,
+) {
+    let Some(save) = save else {
+        return;
+    };
+
+    commands.insert_resource(RestoringSave);
+
+    for e in existing {
+        commands.entity(e).despawn();
+    }
+
+    clock
+        .0
+        .set_elapsed(std::time::Duration::from_secs_f32(save.elapsed_secs));
+    tod.hours = save.time_of_day;
+    next_weather.set(save.weather.into());
+    offset.0 = Vec2::from_array(save.cloud_offset);
+
+    // saved indices become fresh entities
+    let entities: Vec<Entity> = save
+        .ducks
+        .iter()
+        .map(|_| commands.spawn_empty().id())
+        .collect();
+
+    for (saved, &e) in save.ducks.iter().zip(&entities) {
+        let transform = Transform {
+            translation: Vec3::from_array(saved.translation),
+            rotation: Quat::from_array(saved.rotation),
+            scale: Vec3::from_array(saved.scale),
+        };
+        commands.trigger(AddDuck {
+            transform,
+            is_player: saved.is_player,
+            is_child: saved.is_child,
+            entity: Some(e),
+        });
+
+        let mut duck = commands.entity(e);
+        duck.insert(Duck::from(&saved.physics));
+        if let Some(pos) = saved.target {
+            duck.insert(TargetPosition {
+                pos: Vec3::from_array(pos),
+            });
+        }
+        if let Some(color) = saved.family_color {
+            duck.insert(FamilyColor(Srgba::from_f32_array(color).into()));
+        }
+        if let Some(stamina) = saved.stamina {
+            duck.insert(Stamina(stamina));
+        }
+        if let Some(growth) = &saved.growth {
+            duck.insert(Growth {
+                age: growth.age,
+                size: growth.size,
+                speed: growth.speed,
+                independence: 0.0,
+            });
+        }
+        if saved.hatched {
+            duck.insert(Hatched);
+        }
+        if saved.done {
+            duck.insert(Done);
+        }
+        if let Some(reunited) = saved.grown_up {
+            duck.insert(GrownUp { reunited });
+        }
+        if let Some(left) = saved.nesting {
+            duck.insert(Nesting { left });
+        }
+    }
+
+    // relationships once every duck exists
+    for (saved, &e) in save.ducks.iter().zip(&entities) {
+        if let Some(parent) = saved.parent {
+            commands.entity(e).insert(DuckParent(entities[parent]));
+        }
+        if let Some(following) = saved.following {
+            commands.entity(e).insert(Following(entities[following]));
+        }
+    }
+
+    // after the parents, which would otherwise re-derive ducklings' voices
+    for (saved, &e) in save.ducks.iter().zip(&entities) {
+        if let Some(voice) = &saved.voice {
+            commands.entity(e).insert(Voice::from(voice));
+        }
+    }
+
+    // last, as despawning ducks still trips the scoring observers
+    commands.insert_resource(save.score);
+    commands.remove_resource::<RestoringSave>();
+
+    info!("Loaded {} ducks", entities.len());
+    commands.trigger(Notification::new("notify.loaded"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ducks::on_add_duck;
+    use crate::math::GameRng;
+    use crate::score::score_reunion;
+    use bevy::ecs::system::RunSystemOnce;
+
+    /// Every notification shown, in order.
+    #[derive(Resource, Default)]
+    struct Notified(Vec<&'static str>);
+
+    fn headless_app() -> App {
+        let mut app = App::new();
+        app.add_observer(on_add_duck);
+        app.add_observer(score_reunion);
+        app.add_observer(|n: On<Notification>, mut notified: ResMut<Notified>| {
+            notified.0.push(n.key);
+        });
+        app.init_resource::<Notified>();
+        app.init_resource::<GameRng>();
+        app.init_resource::<Assets<Mesh>>();
+        app.init_resource::<Assets<StandardMaterial>>();
+        app.init_resource::<GameClock>();
+        app.insert_resource(TimeOfDay {
+            hours: 10.0,
+            hours_per_second: 0.0,
+        });
+        app.insert_resource(State::new(Weather::Clear));
+        app.init_resource::<NextState<Weather>>();
+        app.insert_resource(NoiseOffset(Vec2::ZERO));
+        app.insert_resource(Score::default());
+        app
+    }
+
+    fn add_duck(app: &mut App, x: f32, is_player: bool, is_child: bool) -> Entity {
+        let world = app.world_mut();
+        let e = world.spawn_empty().id();
+        world.trigger(AddDuck {
+            transform: Transform::from_xyz(x, 0.0, x),
+            is_player,
+            is_child,
+            entity: Some(e),
+        });
+        e
+    }
+
+    /// Different from anything a duck starts out with.
+    fn physics(seed: f32) -> Duck {
+        Duck {
+            is_kicking: true,
+            is_boosting: true,
+            speed_mod: 1.5 + seed,
+            velocity: Vec3::new(seed, 2.0, -3.0),
+            angular_acceleration: 0.25 + seed,
+            angular_velocity: -0.5 + seed,
+            target_head_angle: 0.2 + seed,
+            actual_head_angle: -0.1 + seed,
+            above_sea_level: -0.3 + seed,
+        }
+    }
+
+    fn voice(seed: f32) -> Voice {
+        Voice {
+            sample: 3,
+            base_pitch: 1.25 + seed,
+            pitch_jitter: 0.125,
+            volume: 0.5 + seed,
+        }
+    }
+
+    /// What `save` says about the duck the `i`th duck refers to, so ducks
+    /// can be matched up across saves whatever order they were saved in.
+    fn duck_at(save: &SaveGame, i: Option<usize>) -> Option<[f32; 3]> {
+        i.map(|i| save.ducks[i].translation)
+    }
+
+    #[test]
+    fn save_loads_into_a_fresh_app() {
+        let mut app = headless_app();
+        let player = add_duck(&mut app, 1.0, true, false);
+        let adult = add_duck(&mut app, 5.0, false, false);
+        let duckling = add_duck(&mut app, 3.0, false, true);
+        let sibling = add_duck(&mut app, 9.0, false, true);
+        let grown = add_duck(&mut app, 7.0, false, false);
+        let hatchling = add_duck(&mut app, 11.0, false, true);
+
+        let world = app.world_mut();
+        world.entity_mut(grown).insert((
+            physics(0.4),
+            GrownUp { reunited: true },
+            Nesting { left: 30.0 },
+        ));
+        world
+            .entity_mut(hatchling)
+            .insert((Hatched, DuckParent(grown), Following(grown), Done));
+        world
+            .entity_mut(player)
+            .insert((physics(0.1), Stamina(0.4)));
+        world.entity_mut(adult).insert((
+            physics(0.2),
+            TargetPosition {
+                pos: Vec3::new(7.0, 0.0, -2.0),
+            },
+            voice(0.2),
+            FamilyColor(Color::srgba(0.25, 0.5, 0.75, 1.0)),
+        ));
+        world.entity_mut(duckling).insert((
+            physics(0.3),
+            TargetPosition {
+                pos: Vec3::new(-4.0, 0.0, 6.0),
+            },
+            voice(0.3),
+            Growth {
+                age: 12.5,
+                size: 0.25,
+                speed: 1.75,
+                independence: 0.0,
+            },
+            DuckParent(adult),
+            Following(player),
+            Done,
+        ));
+        // home moments apart from the first, which would be a combo in play
+        world
+            .entity_mut(sibling)
+            .insert((DuckParent(adult), Following(adult), Done));
+        world
+            .resource_mut::<GameClock>()
+            .0
+            .set_elapsed(std::time::Duration::from_secs(42));
+        world.resource_mut::<TimeOfDay>().hours = 19.5;
+        world.insert_resource(State::new(Weather::Fog));
+        world.resource_mut::<NoiseOffset>().0 = Vec2::new(3.0, 4.0);
+        world.resource_mut::<Score>().reunions = 2;
+
+        let before = world.run_system_once(snapshot_game).unwrap();
+        let text = ron::to_string(&before).unwrap();
+        let save: SaveGame = ron::from_str(&text).unwrap();
+
+        let mut app = headless_app();
+        let world = app.world_mut();
+        world
+            .run_system_once_with(restore_game, Some(save))
+            .unwrap();
+
+        assert_eq!(world.resource::<GameClock>().0.elapsed_secs(), 42.0);
+        assert_eq!(world.resource::<TimeOfDay>().hours, 19.5);
+        assert!(matches!(
+            world.resource::<NextState<Weather>>(),
+            NextState::Pending(Weather::Fog)
+        ));
+        assert_eq!(world.resource::<NoiseOffset>().0, Vec2::new(3.0, 4.0));
+        assert_eq!(world.resource::<Score>().reunions, 2);
+        assert_eq!(world.query::<&Duck>().iter(world).count(), 6);
+        // the ducklings already home don't count as reunions all over again
+        assert_eq!(world.resource::<Notified>().0, ["notify.loaded"]);
+
+        // every duck comes back exactly as it was saved
+        let after = world.run_system_once(snapshot_game).unwrap();
+        assert_eq!(after.ducks.len(), before.ducks.len());
+        for saved in &before.ducks {
+            let restored = after
+                .ducks
+                .iter()
+                .find(|d| d.translation == saved.translation)
+                .unwrap_or_else(|| panic!("lost the duck at {:?}", saved.translation));
+            assert_eq!(restored.is_player, saved.is_player);
+            assert_eq!(restored.is_child, saved.is_child);
+            assert_eq!(restored.rotation, saved.rotation);
+            assert_eq!(restored.scale, saved.scale);
+            assert_eq!(restored.physics, saved.physics);
+            assert_eq!(restored.target, saved.target);
+            assert_eq!(
+                duck_at(&after, restored.parent),
+                duck_at(&before, saved.parent)
+            );
+            assert_eq!(
+                duck_at(&after, restored.following),
+                duck_at(&before, saved.following)
+            );
+            assert_eq!(restored.done, saved.done);
+            assert_eq!(restored.voice, saved.voice);
+            assert_eq!(restored.family_color, saved.family_color);
+            assert_eq!(restored.stamina, saved.stamina);
+            assert_eq!(restored.growth, saved.growth);
+            assert_eq!(restored.grown_up, saved.grown_up);
+            assert_eq!(restored.nesting, saved.nesting);
+            assert_eq!(restored.hatched, saved.hatched);
+        }
+
+        // and what was set above made it into the save in the first place
+        let child = before
+            .ducks
+            .iter()
+            .find(|d| d.translation == [3.0, 0.0, 3.0])
+            .unwrap();
+        assert_eq!(child.physics, SavedPhysics::from(&physics(0.3)));
+        assert_eq!(child.target, Some([-4.0, 0.0, 6.0]));
+        assert_eq!(child.voice, Some(SavedVoice::from(&voice(0.3))));
+        assert_eq!(
+            child.growth,
+            Some(SavedGrowth {
+                age: 12.5,
+                size: 0.25,
+                speed: 1.75,
+            })
+        );
+        assert!(child.done);
+        assert_eq!(duck_at(&before, child.following), Some([1.0, 0.0, 1.0]));
+        assert_eq!(duck_at(&before, child.parent), Some([5.0, 0.0, 5.0]));
+        let adult = before
+            .ducks
+            .iter()
+            .find(|d| d.translation == [5.0, 0.0, 5.0])
+            .unwrap();
+        assert_eq!(adult.family_color, Some([0.25, 0.5, 0.75, 1.0]));
+        let player = before.ducks.iter().find(|d| d.is_player).unwrap();
+        assert_eq!(player.stamina, Some(0.4));
+        let grown = before
+            .ducks
+            .iter()
+            .find(|d| d.grown_up == Some(true))
+            .unwrap();
+        assert_eq!(grown.nesting, Some(30.0));
+        let hatchling = before.ducks.iter().find(|d| d.hatched).unwrap();
+        assert_eq!(duck_at(&before, hatchling.parent), Some(grown.translation));
+    }
+
+    #[test]
+    fn old_saves_are_refused() {
+        let mut app = headless_app();
+        let world = app.world_mut();
+        let save = world.run_system_once(snapshot_game).unwrap();
+        assert!(check_version(save).is_ok());
+
+        let mut save = world.run_system_once(snapshot_game).unwrap();
+        save.version = SAVE_VERSION - 1;
+        assert!(check_version(save).is_err());
+    }
+}
@@ -0,0 +1,578 @@
+use bevy::color::palettes::tailwind::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::ducks::{Done, LostLeader};
+use crate::growth::{Brood, Hatched};
+use crate::hud::{GameClock, Notification};
+use crate::level::{Level, StartLevel};
+use crate::locale::Localization;
+use crate::persist::{DataDir, unix_timestamp};
+use crate::player::PlayerDuck;
+use crate::predators::DucklingCaught;
+use crate::savegame::RestoringSave;
+use crate::ui::{UiElement, UiFont, UiState};
+
+pub fn score_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        finish_when_all_reunited.run_if(in_state(UiState::Game)),
+    );
+    app.add_systems(
+        OnEnter(UiState::Results),
+        (record_result, spawn_results_screen).chain(),
+    );
+
+    app.add_observer(reset_score);
+    app.add_observer(score_reunion);
+    app.add_observer(penalize_lost_follower);
+    app.add_observer(penalize_caught_duckling);
+
+    app.insert_resource(Score::default());
+    app.init_resource::<DataDir>();
+}
+
+/// Localization keys used in this module.
+pub const LOCALE_KEYS: &[&str] = &[
+    "notify.combo",
+    "results.title",
+    "results.reunions",
+    "results.combos",
+    "results.lost",
+    "results.caught",
+    "results.time_bonus",
+    "results.total",
+    "results.leaderboard",
+];
+
+const REUNION_POINTS: i32 = 100;
+
+/// Reunions this close together, in seconds, build a combo.
+const COMBO_WINDOW: f32 = 10.0;
+/// Extra points per step of combo.
+const COMBO_POINTS: i32 = 50;
+
+/// Lost for each duckling that stops following the player before it's home.
+const LOST_FOLLOWER_PENALTY: i32 = 25;
+
+/// Lost for each duckling a predator catches.
+const CAUGHT_PENALTY: i32 = 50;
+
+/// Finishing in less than this many seconds earns a time bonus.
+const PAR_TIME: f32 = 300.0;
+const TIME_BONUS_PER_SECOND: f32 = 2.0;
+
+/// Score needed for one, two and three stars, as a fraction of the points
+/// for reuniting every duckling.
+const STAR_THRESHOLDS: [f32; 3] = [0.5, 0.9, 1.2];
+
+const LEADERBOARD_SIZE: usize = 10;
+
+/// Points earned so far this game, kept by category for the results screen.
+#[derive(Resource, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Score {
+    pub reunions: u32,
+    pub combo_points: i32,
+    pub lost_followers: u32,
+    #[serde(default)]
+    pub caught: u32,
+    pub time_bonus: i32,
+    combo: u32,
+    last_reunion: Option<f32>,
+}
+
+impl Score {
+    pub fn reunion_points(&self) -> i32 {
+        self.reunions as i32 * REUNION_POINTS
+    }
+
+    pub fn penalty_points(&self) -> i32 {
+        -(self.lost_followers as i32) * LOST_FOLLOWER_PENALTY
+    }
+
+    pub fn caught_points(&self) -> i32 {
+        -(self.caught as i32) * CAUGHT_PENALTY
+    }
+
+    pub fn total(&self) -> i32 {
+        self.reunion_points()
+            + self.combo_points
+            + self.penalty_points()
+            + self.caught_points()
+            + self.time_bonus
+    }
+}
+
+/// Stars out of three for a score on a level with this many ducklings.
+pub fn stars(score: i32, n_ducklings: usize) -> u8 {
+    let max = (n_ducklings as i32 * REUNION_POINTS).max(1) as f32;
+    STAR_THRESHOLDS
+        .iter()
+        .filter(|t| score as f32 >= *t * max)
+        .count() as u8
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub score: i32,
+    pub stars: u8,
+    pub time_secs: f32,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+/// Best results for one level and seed, highest first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    fn file_name(level: &Level) -> String {
+        format!("leaderboards/{}.ron", level.key())
+    }
+
+    /// Adds an entry, returning its rank if it made the board. Ties go to
+    /// whoever got there first.
+    fn insert(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|e| entry.score > e.score)
+            .unwrap_or(self.entries.len());
+        if rank >= LEADERBOARD_SIZE {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(LEADERBOARD_SIZE);
+        Some(rank)
+    }
+}
+
+/// How the game just finished went.
+#[derive(Resource, Debug)]
+struct GameResult {
+    stars: u8,
+    rank: Option<usize>,
+    leaderboard: Leaderboard,
+}
+
+fn reset_score(_event: On<StartLevel>, mut score: ResMut<Score>) {
+    *score = Score::default();
+}
+
+/// Not for ducklings a loaded save brings back home, nor ones hatched there.
+pub fn score_reunion(
+    event: On<Add, Done>,
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    clock: Res<GameClock>,
+    restoring: Option<Res<RestoringSave>>,
+    hatched: Query<(), With<Hatched>>,
+) {
+    if restoring.is_some() || hatched.contains(event.entity) {
+        return;
+    }
+
+    let now = clock.0.elapsed_secs();
+
+    let in_combo = score.last_reunion.is_some_and(|t| now - t < COMBO_WINDOW);
+    score.combo = if in_combo { score.combo + 1 } else { 0 };
+    score.last_reunion = Some(now);
+
+    score.reunions += 1;
+    if score.combo > 0 {
+        score.combo_points += COMBO_POINTS * score.combo as i32;
+        commands.trigger(Notification::new("notify.combo"));
+    }
+}
+
+/// Only counts ducklings that wandered off, not ones that were caught or
+/// despawned with the level.
+fn penalize_lost_follower(
+    event: On<LostLeader>,
+    mut score: ResMut<Score>,
+    player: Query<Entity, With<PlayerDuck>>,
+) {
+    if player.contains(event.leader) {
+        debug!("Duckling {} stopped following the player", event.duckling);
+        score.lost_followers += 1;
+    }
+}
+
+fn penalize_caught_duckling(_event: On<DucklingCaught>, mut score: ResMut<Score>) {
+    score.caught += 1;
+}
+
+/// Once every duckling is home, or has grown up before it got there.
+fn finish_when_all_reunited(brood: Brood, mut next: ResMut<NextState<UiState>>) {
+    if brood.all_settled() {
+        info!("All ducklings reunited!");
+        next.set(UiState::Results);
+    }
+}
+
+fn record_result(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    clock: Res<GameClock>,
+    level: Res<Level>,
+    data: Res<DataDir>,
+    brood: Brood,
+) {
+    let time_secs = clock.0.elapsed_secs();
+    score.time_bonus = ((PAR_TIME - time_secs).max(0.0) * TIME_BONUS_PER_SECOND) as i32;
+
+    let total = score.total();
+    let stars = stars(total, brood.total());
+
+    let file_name = Leaderboard::file_name(&level);
+    let mut leaderboard = data.load_ron::<Leaderboard>(&file_name).unwrap_or_default();
+    let rank = leaderboard.insert(LeaderboardEntry {
+        score: total,
+        stars,
+        time_secs,
+        timestamp: unix_timestamp(),
+    });
+
+    if let Err(e) = data.save_ron(&file_name, &leaderboard) {
+        error!("Failed to save leaderboard: {}", e);
+    }
+
+    info!("Final score {} ({} stars), rank {:?}", total, stars, rank);
+
+    commands.insert_resource(GameResult {
+        stars,
+        rank,
+        leaderboard,
+    });
+}
+
+fn format_time(secs: f32) -> String {
+    let secs = secs as u32;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+fn spawn_results_screen(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    loc: Res<Localization>,
+    score: Res<Score>,
+    result: Res<GameResult>,
+) {
+    let text = |s: String, size: f32, color: Srgba| {
+        (
+            Text::new(s),
+            TextFont {
+                font: font.0.clone(),
+                font_size: size,
+                ..default()
+            },
+            TextColor(color.into()),
+        )
+    };
+
+    let row = |left: String, right: String, size: f32, color: Srgba| {
+        (
+            Node {
+                width: percent(100.0),
+                justify_content: JustifyContent::SpaceBetween,
+                column_gap: px(24.0),
+                ..default()
+            },
+            children![text(left, size, color), text(right, size, color)],
+        )
+    };
+
+    let screen = commands
+        .spawn((
+            UiElement,
+            Node {
+                position_type: PositionType::Absolute,
+                width: percent(100.0),
+                height: percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(ZINC_950.with_alpha(0.5).into()),
+            GlobalZIndex(10),
+        ))
+        .id();
+
+    let panel = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(px(24.0)),
+                row_gap: px(8.0),
+                min_width: px(360.0),
+                border_radius: BorderRadius::all(px(12.0)),
+                ..default()
+            },
+            BackgroundColor(ZINC_900.with_alpha(0.9).into()),
+            ChildOf(screen),
+        ))
+        .id();
+
+    commands.spawn((
+        text(loc.get("results.title").to_owned(), 36.0, Srgba::WHITE),
+        ChildOf(panel),
+    ));
+
+    let stars = commands
+        .spawn((
+            Node {
+                column_gap: px(12.0),
+                margin: UiRect::vertical(px(8.0)),
+                ..default()
+            },
+            ChildOf(panel),
+        ))
+        .id();
+    for i in 0..3 {
+        let color = if i < result.stars {
+            AMBER_300
+        } else {
+            ZINC_700
+        };
+        commands.spawn((
+            Node {
+                width: px(20.0),
+                height: px(20.0),
+                ..default()
+            },
+            UiTransform::from_rotation(Rot2::degrees(45.0)),
+            BackgroundColor(color.into()),
+            ChildOf(stars),
+        ));
+    }
+
+    // how the score was earned
+    let breakdown = [
+        (
+            loc.format("results.reunions", &[("count", &score.reunions)]),
+            score.reunion_points(),
+        ),
+        (loc.get("results.combos").to_owned(), score.combo_points),
+        (
+            loc.format("results.lost", &[("count", &score.lost_followers)]),
+            score.penalty_points(),
+        ),
+        (
+            loc.format("results.caught", &[("count", &score.caught)]),
+            score.caught_points(),
+        ),
+        (loc.get("results.time_bonus").to_owned(), score.time_bonus),
+    ];
+    for (label, points) in breakdown {
+        commands.spawn((
+            row(label, format!("{:+}", points), 20.0, ZINC_300),
+            ChildOf(panel),
+        ));
+    }
+    commands.spawn((
+        row(
+            loc.get("results.total").to_owned(),
+            score.total().to_string(),
+            26.0,
+            Srgba::WHITE,
+        ),
+        ChildOf(panel),
+    ));
+
+    commands.spawn((
+        Node {
+            margin: UiRect::top(px(16.0)),
+            ..default()
+        },
+        children![text(
+            loc.get("results.leaderboard").to_owned(),
+            22.0,
+            Srgba::WHITE
+        )],
+        ChildOf(panel),
+    ));
+    for (rank, entry) in result.leaderboard.entries.iter().enumerate() {
+        let color = if result.rank == Some(rank) {
+            AMBER_300
+        } else {
+            ZINC_300
+        };
+        commands.spawn((
+            row(
+                format!("{}. {}", rank + 1, entry.score),
+                format_time(entry.time_secs),
+                18.0,
+                color,
+            ),
+            ChildOf(panel),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::child_rel::Following;
+    use crate::ducks::Duckling;
+    use crate::growth::GrownUp;
+
+    fn entry(score: i32) -> LeaderboardEntry {
+        LeaderboardEntry {
+            score,
+            stars: 0,
+            time_secs: 0.0,
+            timestamp: 0,
+        }
+    }
+
+    fn scores(board: &Leaderboard) -> Vec<i32> {
+        board.entries.iter().map(|e| e.score).collect()
+    }
+
+    #[test]
+    fn stars_scale_with_the_ducklings() {
+        let all = 10 * REUNION_POINTS;
+        assert_eq!(stars(0, 10), 0);
+        assert_eq!(stars(all / 2 - 1, 10), 0);
+        assert_eq!(stars(all / 2, 10), 1);
+        assert_eq!(stars(all * 9 / 10, 10), 2);
+        assert_eq!(stars(all * 12 / 10, 10), 3);
+        assert_eq!(stars(i32::MAX, 10), 3);
+    }
+
+    #[test]
+    fn stars_survive_no_ducklings_and_negative_scores() {
+        assert_eq!(stars(-500, 10), 0);
+        assert_eq!(stars(-500, 0), 0);
+        assert_eq!(stars(0, 0), 0);
+        assert_eq!(stars(REUNION_POINTS, 0), 3);
+    }
+
+    #[test]
+    fn leaderboard_ranks_highest_first() {
+        let mut board = Leaderboard::default();
+        assert_eq!(board.insert(entry(100)), Some(0));
+        assert_eq!(board.insert(entry(300)), Some(0));
+        assert_eq!(board.insert(entry(-50)), Some(2));
+        assert_eq!(board.insert(entry(200)), Some(1));
+        assert_eq!(scores(&board), [300, 200, 100, -50]);
+    }
+
+    #[test]
+    fn leaderboard_ties_keep_the_earlier_entry_ahead() {
+        let mut board = Leaderboard::default();
+        board.insert(LeaderboardEntry {
+            timestamp: 1,
+            ..entry(100)
+        });
+        let rank = board.insert(LeaderboardEntry {
+            timestamp: 2,
+            ..entry(100)
+        });
+        assert_eq!(rank, Some(1));
+        let order: Vec<u64> = board.entries.iter().map(|e| e.timestamp).collect();
+        assert_eq!(order, [1, 2]);
+    }
+
+    #[test]
+    fn leaderboard_keeps_only_the_best() {
+        let mut board = Leaderboard::default();
+        for score in 1..=LEADERBOARD_SIZE as i32 {
+            board.insert(entry(score * 10));
+        }
+        assert_eq!(board.entries.len(), LEADERBOARD_SIZE);
+
+        // too low, or only equal to the lowest, doesn't make it
+        assert_eq!(board.insert(entry(5)), None);
+        assert_eq!(board.insert(entry(10)), None);
+        assert_eq!(board.entries.len(), LEADERBOARD_SIZE);
+
+        assert_eq!(board.insert(entry(55)), Some(5));
+        assert_eq!(board.entries.len(), LEADERBOARD_SIZE);
+        assert_eq!(board.entries.last().unwrap().score, 20);
+    }
+
+    #[test]
+    fn quick_reunions_build_a_combo() {
+        let mut app = App::new();
+        app.add_observer(score_reunion);
+        app.insert_resource(Score::default());
+        app.init_resource::<GameClock>();
+
+        let mut reunite_at = |secs: f32| {
+            let world = app.world_mut();
+            world
+                .resource_mut::<GameClock>()
+                .0
+                .set_elapsed(std::time::Duration::from_secs_f32(secs));
+            world.spawn(Duckling).insert(Done);
+            world.resource::<Score>().clone()
+        };
+
+        assert_eq!(reunite_at(0.0).combo_points, 0);
+        assert_eq!(reunite_at(5.0).combo_points, COMBO_POINTS);
+        assert_eq!(reunite_at(9.0).combo_points, COMBO_POINTS * 3);
+        // too long since the last one, so the combo starts over
+        let score = reunite_at(9.0 + COMBO_WINDOW);
+        assert_eq!(score.combo_points, COMBO_POINTS * 3);
+        assert_eq!(score.reunions, 4);
+        let score = reunite_at(10.0 + COMBO_WINDOW);
+        assert_eq!(score.combo_points, COMBO_POINTS * 4);
+        assert_eq!(score.total(), 5 * REUNION_POINTS + COMBO_POINTS * 4);
+    }
+
+    #[test]
+    fn finishes_once_nobody_is_left_to_reunite() {
+        let mut app = App::new();
+        app.add_systems(Update, finish_when_all_reunited);
+        app.init_resource::<NextState<UiState>>();
+        let finished = |app: &App| {
+            matches!(
+                app.world().resource::<NextState<UiState>>(),
+                NextState::Pending(UiState::Results)
+            )
+        };
+
+        app.update();
+        assert!(!finished(&app), "finished without any ducklings");
+
+        // one grew up before getting home, one after
+        let world = app.world_mut();
+        world.spawn(GrownUp { reunited: false });
+        world.spawn(GrownUp { reunited: true });
+        let duckling = world.spawn(Duckling).id();
+        app.update();
+        assert!(!finished(&app));
+
+        app.world_mut().entity_mut(duckling).insert(Done);
+        app.update();
+        assert!(finished(&app));
+    }
+
+    #[test]
+    fn only_wandering_off_costs_a_follower() {
+        let mut app = App::new();
+        app.add_observer(penalize_lost_follower);
+        app.insert_resource(Score::default());
+
+        let player = app.world_mut().spawn(PlayerDuck).id();
+        let adult = app.world_mut().spawn_empty().id();
+        let caught = app.world_mut().spawn((Duckling, Following(player))).id();
+        let despawned = app.world_mut().spawn((Duckling, Following(player))).id();
+
+        app.world_mut().entity_mut(caught).remove::<Following>();
+        app.world_mut().despawn(despawned);
+        assert_eq!(app.world().resource::<Score>().lost_followers, 0);
+
+        for leader in [adult, player] {
+            app.world_mut().trigger(LostLeader {
+                duckling: caught,
+                leader,
+            });
+        }
+        assert_eq!(app.world().resource::<Score>().lost_followers, 1);
+    }
+}
@@ -1,16 +1,21 @@
-use bevy::{color::palettes::tailwind::*, prelude::*};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
 use bevy_rich_text3d::{Text3d, Text3dStyling, TextAtlas};
 
 use crate::{
-    despawn_after::DespawnAfter,
-    math::{random_range, random_vec},
+    locale::Localization, math::cosmetic_random_vec, photo::PhotoState, quacks::QuackKind,
 };
 
 pub fn text_bubble_plugin(app: &mut App) {
+    app.add_systems(Startup, setup_bubble_material);
     app.add_systems(
         FixedUpdate,
         (
             handle_quack_messages,
+            stack_quack_bubbles,
+            animate_quack_bubbles,
+            // photo mode hides and shows bubbles itself
+            hide_distant_quack_bubbles.run_if(in_state(PhotoState::Off)),
             point_text_2d_towards_camera,
             sync_transforms_to_parent,
         )
@@ -22,52 +27,83 @@ pub fn text_bubble_plugin(app: &mut App) {
     app.add_message::<Quack>();
 }
 
-#[derive(Debug, Clone, Copy)]
-enum QuackKind {
-    Noise,
-    Info,
-    Important,
-}
+/// Localization keys used in this module.
+pub const LOCALE_KEYS: &[&str] = &["score.placeholder"];
 
 #[derive(Message, Debug, Clone)]
 pub struct Quack {
     pub entity: Entity,
-    text: String,
-    kind: QuackKind,
+    pub kind: QuackKind,
+    text_key: &'static str,
 }
 
 impl Quack {
-    pub fn noise(entity: Entity, text: impl Into<String>) -> Self {
+    /// A quack of the given kind, saying something from its text pool.
+    pub fn new(entity: Entity, kind: QuackKind) -> Self {
         Self {
             entity,
-            text: text.into(),
-            kind: QuackKind::Noise,
+            kind,
+            text_key: kind.random_text_key(),
         }
     }
+}
 
-    pub fn info(entity: Entity, text: impl Into<String>) -> Self {
-        Self {
-            entity,
-            text: text.into(),
-            kind: QuackKind::Info,
-        }
-    }
+/// Most quack bubbles shown over one duck; older ones are faded out early.
+const MAX_BUBBLES_PER_DUCK: usize = 3;
 
-    pub fn important(entity: Entity, text: impl Into<String>) -> Self {
-        Self {
-            entity,
-            text: text.into(),
-            kind: QuackKind::Important,
-        }
-    }
-}
+/// Height of the lowest bubble above a duck, and the gap between stacked ones.
+const BUBBLE_BASE_HEIGHT: f32 = 1.4;
+const BUBBLE_SPACING: f32 = 0.6;
+
+const BUBBLE_FADE_IN: f32 = 0.15;
+const BUBBLE_FADE_OUT: f32 = 0.4;
+
+/// Quack bubbles further than this from the camera are hidden.
+const MAX_BUBBLE_DISTANCE: f32 = 120.0;
 
+/// Root of a bubble of text floating over a duck.
 #[derive(Component)]
-struct TextBubble {
+pub struct TextBubble {
     parent: Entity,
     offset: Vec3,
 }
 
+/// A transient bubble showing a quack. Lives on the [`TextBubble`] root.
+#[derive(Component)]
+struct QuackBubble {
+    /// The text entity, a child of the root.
+    label: Entity,
+    color: Srgba,
+    age: f32,
+    lifetime: f32,
+    /// Position in the duck's stack, 0 being the newest and lowest.
+    slot: usize,
+}
+
+impl QuackBubble {
+    /// Grows and fades in, holds, then shrinks and fades out. In [0, 1].
+    fn presence(&self) -> f32 {
+        let fade_in = (self.age / BUBBLE_FADE_IN).min(1.0);
+        let fade_out = ((self.lifetime - self.age) / BUBBLE_FADE_OUT).clamp(0.0, 1.0);
+        fade_in.min(fade_out)
+    }
+}
+
+/// Every text bubble is drawn with this one material; per-bubble color
+/// comes from the text's vertex colors.
+#[derive(Resource)]
+struct BubbleMaterial(Handle<StandardMaterial>);
+
+fn setup_bubble_material(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    let material = materials.add(StandardMaterial {
+        base_color_texture: Some(TextAtlas::DEFAULT_IMAGE.clone()),
+        alpha_mode: AlphaMode::Blend,
+        emissive: Srgba::WHITE.with_alpha(0.3).into(),
+        ..Default::default()
+    });
+    commands.insert_resource(BubbleMaterial(material));
+}
+
 #[derive(Event)]
 pub struct SpawnScoreMarker {
     pub duck: Entity,
@@ -81,9 +117,10 @@ pub struct ScoreLabel {
 fn on_spawn_adult_duck(
     event: On<SpawnScoreMarker>,
     mut commands: Commands,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    material: Res<BubbleMaterial>,
+    loc: Res<Localization>,
 ) {
-    let p = random_vec(2.0, 15.0);
+    let p = cosmetic_random_vec(2.0, 15.0);
     let tf = Transform::from_xyz(p.x, 4.0, p.y);
     let y = 2.5;
 
@@ -94,7 +131,7 @@ fn on_spawn_adult_duck(
                 offset: Vec3::new(0.0, y, 0.0),
             },
             tf,
-            InheritedVisibility::VISIBLE,
+            Visibility::default(),
         ))
         .id();
 
@@ -104,7 +141,7 @@ fn on_spawn_adult_duck(
 
     let e = commands
         .spawn((
-            Text3d::new("BIGMODE"),
+            Text3d::new(loc.get("score.placeholder")),
             ScoreLabel { duck: event.duck },
             Text3dStyling {
                 size: 80.,
@@ -116,12 +153,7 @@ fn on_spawn_adult_duck(
             },
             Transform::from_xyz(0.0, 0.0, 0.01),
             Mesh3d::default(),
-            MeshMaterial3d(materials.add(StandardMaterial {
-                base_color_texture: Some(TextAtlas::DEFAULT_IMAGE.clone()),
-                alpha_mode: AlphaMode::Blend,
-                emissive: Srgba::WHITE.with_alpha(0.3).into(),
-                ..Default::default()
-            })),
+            MeshMaterial3d(material.0.clone()),
             InheritedVisibility::VISIBLE,
         ))
         .id();
@@ -132,63 +164,122 @@ fn on_spawn_adult_duck(
 fn handle_quack_messages(
     mut messages: MessageReader<Quack>,
     mut commands: Commands,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    material: Res<BubbleMaterial>,
+    loc: Res<Localization>,
 ) {
     for quack in messages.read() {
-        let p = random_vec(2.0, 15.0);
-        let tf = Transform::from_xyz(p.x, 4.0, p.y);
-
-        let xz = random_vec(0.03, 0.3);
-        let y = random_range(1.2..=2.0);
-
-        let root = commands
-            .spawn((
-                DespawnAfter::new(std::time::Duration::from_secs(2)),
-                TextBubble {
-                    parent: quack.entity,
-                    offset: Vec3::new(xz.x, y, xz.y),
-                },
-                tf,
-                InheritedVisibility::VISIBLE,
-            ))
-            .id();
-
-        let color = match quack.kind {
-            QuackKind::Info => Srgba::new(1., 1., 1., 1.),
-            QuackKind::Noise => Srgba::WHITE.with_alpha(0.4),
-            QuackKind::Important => BLUE_700,
-        };
-
-        let world_scale = match quack.kind {
-            QuackKind::Noise => 0.17,
-            QuackKind::Info => 0.23,
-            QuackKind::Important => 0.4,
-        };
+        let spec = quack.kind.spec();
 
-        let e = commands
+        let label = commands
             .spawn((
-                Text3d::new(quack.text.clone()),
+                Text3d::new(loc.get(quack.text_key)),
                 Text3dStyling {
                     size: 80.,
-                    color,
-                    world_scale: Some(Vec2::splat(world_scale)),
+                    color: spec.color.with_alpha(0.0),
+                    world_scale: Some(Vec2::splat(spec.scale)),
                     layer_offset: 0.001,
                     font: "SNPro-Regular".into(),
                     ..Default::default()
                 },
                 Transform::from_xyz(0.0, 0.0, 0.01),
                 Mesh3d::default(),
-                MeshMaterial3d(materials.add(StandardMaterial {
-                    base_color_texture: Some(TextAtlas::DEFAULT_IMAGE.clone()),
-                    alpha_mode: AlphaMode::Blend,
-                    emissive: Srgba::WHITE.with_alpha(0.3).into(),
-                    ..Default::default()
-                })),
+                MeshMaterial3d(material.0.clone()),
                 InheritedVisibility::VISIBLE,
             ))
             .id();
 
-        commands.entity(root).add_child(e);
+        // a little sideways jitter so repeated quacks don't line up exactly
+        let xz = cosmetic_random_vec(0.03, 0.3);
+
+        commands
+            .spawn((
+                TextBubble {
+                    parent: quack.entity,
+                    offset: Vec3::new(xz.x, BUBBLE_BASE_HEIGHT, xz.y),
+                },
+                QuackBubble {
+                    label,
+                    color: spec.color,
+                    age: 0.0,
+                    lifetime: spec.lifetime,
+                    slot: 0,
+                },
+                Transform::from_scale(Vec3::ZERO),
+                Visibility::default(),
+            ))
+            .add_child(label);
+    }
+}
+
+/// Pushes older bubbles up above newer ones, and cuts short any beyond the
+/// per-duck limit.
+fn stack_quack_bubbles(mut bubbles: Query<(Entity, &TextBubble, &mut QuackBubble)>) {
+    let mut by_duck: HashMap<Entity, Vec<(f32, Entity)>> = HashMap::new();
+    for (e, text, bubble) in &bubbles {
+        by_duck
+            .entry(text.parent)
+            .or_default()
+            .push((bubble.age, e));
+    }
+
+    for mut stack in by_duck.into_values() {
+        stack.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (slot, (_, e)) in stack.into_iter().enumerate() {
+            let Ok((_, _, mut bubble)) = bubbles.get_mut(e) else {
+                continue;
+            };
+            bubble.slot = slot;
+            if slot >= MAX_BUBBLES_PER_DUCK {
+                bubble.lifetime = bubble.lifetime.min(bubble.age + BUBBLE_FADE_OUT);
+            }
+        }
+    }
+}
+
+fn animate_quack_bubbles(
+    mut commands: Commands,
+    bubbles: Query<(Entity, &mut TextBubble, &mut QuackBubble, &mut Transform)>,
+    mut labels: Query<&mut Text3dStyling>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_secs();
+    for (e, mut text, mut bubble, mut tf) in bubbles {
+        bubble.age += dt;
+        if bubble.age >= bubble.lifetime {
+            commands.entity(e).despawn();
+            continue;
+        }
+
+        let target_y = BUBBLE_BASE_HEIGHT + bubble.slot as f32 * BUBBLE_SPACING;
+        text.offset.y += (target_y - text.offset.y) * (10.0 * dt).min(1.0);
+
+        let presence = bubble.presence();
+        tf.scale = Vec3::splat(0.5 + 0.5 * presence);
+
+        // only touch the styling while fading, since it rebuilds the text mesh
+        if let Ok(mut styling) = labels.get_mut(bubble.label) {
+            let color = bubble.color.with_alpha(bubble.color.alpha * presence);
+            if styling.color != color {
+                styling.color = color;
+            }
+        }
+    }
+}
+
+/// Hides quack bubbles that are behind the camera or too far away to read.
+fn hide_distant_quack_bubbles(
+    bubbles: Query<(&Transform, &mut Visibility), With<QuackBubble>>,
+    camera: Single<&Transform, (With<Camera3d>, Without<QuackBubble>)>,
+) {
+    let forward = camera.forward();
+    for (tf, mut vis) in bubbles {
+        let to_bubble = tf.translation - camera.translation;
+        let shown = to_bubble.length() < MAX_BUBBLE_DISTANCE && to_bubble.dot(*forward) > 0.0;
+        vis.set_if_neq(if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
     }
 }
 
@@ -203,12 +294,16 @@ fn point_text_2d_towards_camera(
 }
 
 fn sync_transforms_to_parent(
-    text: Query<(&mut Transform, &TextBubble)>,
+    mut commands: Commands,
+    text: Query<(Entity, &mut Transform, &TextBubble)>,
     ducks: Query<&Transform, Without<TextBubble>>,
-) -> Result {
-    for (mut tf, bubble) in text {
-        let parent = ducks.get(bubble.parent)?;
+) {
+    for (e, mut tf, bubble) in text {
+        // the duck is gone, e.g. replaced by loading a saved game
+        let Ok(parent) = ducks.get(bubble.parent) else {
+            commands.entity(e).despawn();
+            continue;
+        };
         tf.translation = parent.translation.with_y(0.0) + bubble.offset;
     }
-    Ok(())
 }
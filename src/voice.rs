@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use crate::audio::{AudioSettings, Bus, BusSound};
+use crate::child_rel::DuckParent;
+use crate::math::cosmetic_random_range;
+use crate::quacks::QuackKind;
+use crate::text_bubble::Quack;
+
+pub fn voice_plugin(app: &mut App) {
+    app.add_systems(FixedUpdate, spawn_sounds_on_quack);
+
+    app.add_observer(inherit_voice_from_true_parent);
+}
+
+/// Most quacks that may be audible at once; any more are only shown as text.
+const MAX_QUACK_VOICES: usize = 6;
+
+/// How much higher a duckling sounds than its parent.
+const DUCKLING_PITCH: std::ops::RangeInclusive<f32> = 1.35..=1.5;
+
+/// What a particular duck sounds like. Stays the same for the duck's whole
+/// life, so a family can be picked out by ear.
+#[derive(Component, Debug, Clone)]
+pub struct Voice {
+    /// Which entry of each quack kind's sound bank this duck uses.
+    pub sample: usize,
+    /// Playback speed every quack is centered on.
+    pub base_pitch: f32,
+    /// Fraction the pitch may wander from `base_pitch` on any one quack.
+    pub pitch_jitter: f32,
+    pub volume: f32,
+}
+
+impl Voice {
+    pub fn random() -> Self {
+        Self {
+            sample: cosmetic_random_range(0..16),
+            base_pitch: cosmetic_random_range(0.85..=1.15),
+            pitch_jitter: cosmetic_random_range(0.02..=0.06),
+            volume: cosmetic_random_range(0.8..=1.0),
+        }
+    }
+
+    /// A higher, slightly quieter take on `parent`.
+    pub fn child_of(parent: &Voice) -> Self {
+        Self {
+            sample: parent.sample,
+            base_pitch: parent.base_pitch * cosmetic_random_range(DUCKLING_PITCH),
+            pitch_jitter: parent.pitch_jitter,
+            volume: parent.volume * 0.8,
+        }
+    }
+
+    fn sound(&self, kind: QuackKind) -> &'static str {
+        let bank = kind.spec().sounds;
+        bank[self.sample % bank.len()]
+    }
+
+    fn quack_speed(&self, kind: QuackKind) -> f32 {
+        // how many quacks get a voice depends on playback, so keep this off
+        // the game RNG
+        let jitter = cosmetic_random_range(-self.pitch_jitter..=self.pitch_jitter);
+        self.base_pitch * kind.spec().pitch * (1.0 + jitter)
+    }
+}
+
+fn inherit_voice_from_true_parent(
+    event: On<Insert, DuckParent>,
+    mut commands: Commands,
+    parents: Query<&DuckParent>,
+    voices: Query<&Voice>,
+) -> Result {
+    let parent = parents.get(event.entity)?;
+    let voice = voices.get(parent.0)?;
+    commands.entity(event.entity).insert(Voice::child_of(voice));
+    Ok(())
+}
+
+#[derive(Component)]
+struct QuackSound;
+
+fn spawn_sounds_on_quack(
+    mut messages: MessageReader<Quack>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mixer: Res<AudioSettings>,
+    voices: Query<&Voice>,
+    playing: Query<(), With<QuackSound>>,
+) {
+    let mut n_playing = playing.iter().count();
+
+    // when there are more quacks than voices, the important ones win
+    let mut quacks: Vec<&Quack> = messages.read().collect();
+    quacks.sort_by_key(|q| std::cmp::Reverse(q.kind.spec().priority));
+
+    for quack in quacks {
+        if n_playing >= MAX_QUACK_VOICES {
+            continue;
+        }
+
+        let Ok(voice) = voices.get(quack.entity) else {
+            continue;
+        };
+
+        n_playing += 1;
+
+        let sound = commands
+            .spawn((
+                Transform::IDENTITY,
+                AudioPlayer::new(asset_server.load(voice.sound(quack.kind))),
+                PlaybackSettings::DESPAWN
+                    .with_spatial(true)
+                    .with_speed(voice.quack_speed(quack.kind))
+                    .with_volume(mixer.volume(Bus::Voice, voice.volume)),
+                BusSound::new(Bus::Voice, voice.volume),
+                QuackSound,
+            ))
+            .id();
+
+        commands.entity(quack.entity).add_child(sound);
+    }
+}
@@ -1,19 +1,25 @@
-use bevy::color::palettes::tailwind::*;
-use bevy::{audio::Volume, prelude::*};
+use bevy::prelude::*;
 
-use crate::math::random_vec;
+use crate::audio::{AudioSettings, Bus, BusSound, DucksAmbience};
+use crate::clouds::{SetCloudColor, SetCloudCover, SetWindSpeed};
+use crate::math::{GameRng, cosmetic_random_chance, cosmetic_random_vec};
 use crate::particles::RippleParticle;
-use crate::{
-    clouds::{SetCloudColor, SetWindSpeed},
-    math::{random_chance, random_range},
-};
+use crate::player::PlayerInput;
 
 pub fn weather_plugin(app: &mut App) {
     // app.add_systems(Startup, add_rain_sounds);
-    app.add_systems(Startup, (add_sunlight, set_sky_color));
+    app.add_systems(Startup, add_sunlight);
 
-    app.add_systems(Update, (spawn_lightning_on_l, toggle_weather_on_m));
-    app.add_systems(FixedUpdate, update_lightning);
+    app.add_systems(
+        FixedUpdate,
+        (
+            spawn_lightning_on_input,
+            cycle_weather_on_input,
+            update_lightning,
+            drift_storm_cells,
+        )
+            .chain(),
+    );
 
     app.add_systems(
         FixedUpdate,
@@ -21,16 +27,19 @@ pub fn weather_plugin(app: &mut App) {
             randomly_spawn_lightning,
             trigger_random_ripples_from_raindrops,
         )
+            .chain()
             .run_if(in_state(Weather::Thunderstorm)),
     );
 
+    app.add_systems(OnEnter(Weather::Clear), on_clear_weather);
+    app.add_systems(OnEnter(Weather::Fog), on_fog);
     app.add_systems(
-        OnEnter(Weather::Clear),
-        (on_clear_weather, remove_rain_sounds, brighten_sun),
+        OnEnter(Weather::Thunderstorm),
+        (on_thunderstorm, add_rain_sounds, spawn_storm_cells),
     );
     app.add_systems(
-        OnEnter(Weather::Thunderstorm),
-        (on_thunderstorm, add_rain_sounds, darken_sun),
+        OnExit(Weather::Thunderstorm),
+        (remove_rain_sounds, remove_storm_cells),
     );
 
     app.add_observer(on_lightning);
@@ -41,59 +50,69 @@ pub fn weather_plugin(app: &mut App) {
 #[derive(States, Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum Weather {
     Clear,
+    Fog,
     Thunderstorm,
 }
 
-fn on_clear_weather(mut commands: Commands, mut color: ResMut<ClearColor>) {
+impl Weather {
+    pub const ALL: [Weather; 3] = [Weather::Clear, Weather::Fog, Weather::Thunderstorm];
+}
+
+fn on_clear_weather(mut commands: Commands) {
     info!("Clear weather!");
     commands.trigger(SetWindSpeed(0.3));
     commands.trigger(SetCloudColor(Srgba::gray(0.95).into()));
+    commands.trigger(SetCloudCover {
+        coverage: 0.35,
+        density: 0.6,
+    });
+}
 
-    color.0 = BLUE_300.into();
+fn on_fog(mut commands: Commands) {
+    info!("Fog!");
+    commands.trigger(SetWindSpeed(0.1));
+    commands.trigger(SetCloudColor(Srgba::gray(0.7).into()));
+    commands.trigger(SetCloudCover {
+        coverage: 0.7,
+        density: 0.8,
+    });
 }
 
-fn on_thunderstorm(mut commands: Commands, mut color: ResMut<ClearColor>) {
+fn on_thunderstorm(mut commands: Commands) {
     info!("Thunderstorm!");
     commands.trigger(SetWindSpeed(7.0));
     commands.trigger(SetCloudColor(Srgba::gray(0.2).into()));
-
-    color.0 = GRAY_700.into();
-}
-
-fn brighten_sun(mut sun: Single<&mut DirectionalLight, With<Sun>>) {
-    sun.illuminance = light_consts::lux::OVERCAST_DAY;
-    sun.color = Color::WHITE;
-}
-
-fn darken_sun(mut sun: Single<&mut DirectionalLight, With<Sun>>) {
-    sun.illuminance = light_consts::lux::FULL_MOON_NIGHT;
-    sun.color = Color::BLACK;
+    commands.trigger(SetCloudCover {
+        coverage: 0.9,
+        density: 1.0,
+    });
 }
 
 fn trigger_random_ripples_from_raindrops(mut commands: Commands) {
     for _ in 0..100 {
-        if random_chance(0.1) {
-            let p = random_vec(0.0, 300.0);
+        if cosmetic_random_chance(0.1) {
+            let p = cosmetic_random_vec(0.0, 300.0);
             let tf = Transform::from_xyz(p.x, 0.05, p.y);
             commands.spawn((RippleParticle::default(), tf));
         }
     }
 }
 
-fn randomly_spawn_lightning(mut commands: Commands) {
-    if random_chance(0.001) {
+fn randomly_spawn_lightning(mut commands: Commands, mut rng: ResMut<GameRng>) {
+    if rng.chance(0.001) {
         commands.trigger(LightningEvent);
     }
 }
 
-fn toggle_weather_on_m(
-    keys: Res<ButtonInput<KeyCode>>,
+fn cycle_weather_on_input(
+    input: Res<PlayerInput>,
     state: Res<State<Weather>>,
     mut next: ResMut<NextState<Weather>>,
 ) {
-    if keys.just_pressed(KeyCode::KeyM) {
+    if input.cycle_weather {
         match **state {
-            Weather::Clear => next.set(Weather::Thunderstorm),
+            Weather::Clear => next.set(Weather::Fog),
+            Weather::Fog => next.set(Weather::Thunderstorm),
             Weather::Thunderstorm => next.set(Weather::Clear),
         }
     }
@@ -102,15 +121,53 @@ fn toggle_weather_on_m(
 #[derive(Event)]
 pub struct LightningEvent;
 
-#[derive(Component)]
-struct Lightning;
+const NUM_STORM_CELLS: usize = 3;
 
-fn set_sky_color(mut color: ResMut<ClearColor>) {
-    color.0 = BLUE_300.into();
+/// The heart of a thunderstorm, where lightning strikes. Drifts across the
+/// lake while the storm lasts.
+#[derive(Component, Debug)]
+pub struct StormCell {
+    pub radius: f32,
+    velocity: Vec2,
 }
 
+fn spawn_storm_cells(mut commands: Commands, mut rng: ResMut<GameRng>) {
+    for _ in 0..NUM_STORM_CELLS {
+        let p = rng.vec(0.0, 150.0);
+        commands.spawn((
+            StormCell {
+                radius: rng.range(30.0..70.0),
+                velocity: rng.vec(1.0, 4.0),
+            },
+            Transform::from_xyz(p.x, 0.0, p.y),
+        ));
+    }
+}
+
+fn remove_storm_cells(mut commands: Commands, cells: Query<Entity, With<StormCell>>) {
+    for e in cells {
+        commands.entity(e).despawn();
+    }
+}
+
+fn drift_storm_cells(cells: Query<(&mut StormCell, &mut Transform)>, time: Res<Time>) {
+    for (mut cell, mut tf) in cells {
+        tf.translation += Vec3::new(cell.velocity.x, 0.0, cell.velocity.y) * time.delta_secs();
+
+        // turn back towards the lake once well past it
+        if tf.translation.xz().length() > 250.0 {
+            let home = -tf.translation.xz().normalize() * cell.velocity.length();
+            cell.velocity = home;
+        }
+    }
+}
+
+/// The flash of a strike, while it lasts.
+#[derive(Component)]
+pub struct Lightning;
+
 #[derive(Component)]
-struct Sun;
+pub struct Sun;
 
 fn add_sunlight(mut commands: Commands) {
     commands.spawn((
@@ -123,10 +180,15 @@ fn add_sunlight(mut commands: Commands) {
 #[derive(Component)]
 struct RainSound;
 
-fn add_rain_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn add_rain_sounds(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mixer: Res<AudioSettings>,
+) {
     commands.spawn((
         AudioPlayer::new(asset_server.load("rain.ogg")),
-        PlaybackSettings::LOOP.with_volume(Volume::Linear(0.3)),
+        PlaybackSettings::LOOP.with_volume(mixer.volume(Bus::Ambience, 0.3)),
+        BusSound::new(Bus::Ambience, 0.3),
         RainSound,
     ));
 }
@@ -140,11 +202,21 @@ fn remove_rain_sounds(mut commands: Commands, sounds: Query<Entity, With<RainSou
 fn on_lightning(
     _event: On<LightningEvent>,
     mut commands: Commands,
+    mut rng: ResMut<GameRng>,
     asset_server: Res<AssetServer>,
+    mixer: Res<AudioSettings>,
+    cells: Query<(&StormCell, &Transform)>,
 ) {
-    let x = random_range(-100.0..100.0);
-    let z = random_range(-100.0..100.0);
-    let y = random_range(20.0..100.0);
+    // strike within a storm cell if there is one
+    let cells: Vec<_> = cells.iter().collect();
+    let (x, z) = if cells.is_empty() {
+        (rng.range(-100.0..100.0), rng.range(-100.0..100.0))
+    } else {
+        let (cell, tf) = cells[rng.range(0..cells.len())];
+        let p = tf.translation.xz() + rng.vec(0.0, cell.radius);
+        (p.x, p.y)
+    };
+    let y = rng.range(20.0..100.0);
 
     info!("Lightning: {} {} {}", x, y, z);
 
@@ -163,7 +235,9 @@ fn on_lightning(
 
     commands.spawn((
         AudioPlayer::new(asset_server.load("thunder1.ogg")),
-        PlaybackSettings::DESPAWN,
+        PlaybackSettings::DESPAWN.with_volume(mixer.volume(Bus::Sfx, 1.0)),
+        BusSound::new(Bus::Sfx, 1.0),
+        DucksAmbience,
     ));
 }
 
@@ -181,8 +255,8 @@ fn update_lightning(
     }
 }
 
-fn spawn_lightning_on_l(mut commands: Commands, keys: Res<ButtonInput<KeyCode>>) {
-    if keys.pressed(KeyCode::ControlLeft) && keys.just_pressed(KeyCode::KeyL) {
+fn spawn_lightning_on_input(mut commands: Commands, input: Res<PlayerInput>) {
+    if input.lightning {
         commands.trigger(LightningEvent);
     }
 }